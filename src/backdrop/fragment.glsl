@@ -0,0 +1,15 @@
+#include "common/header.glsl"
+#include "common/shading.glsl"
+
+in vec3 world_position;
+in vec3 world_normal;
+
+uniform vec3 camera_position;
+
+out vec4 pix_color;
+
+void main() {
+    vec3 n = normalize(world_normal);
+    vec3 v = normalize(camera_position - world_position);
+    pix_color = shade(world_position, n, v, vec3(0.0));
+}
@@ -0,0 +1,134 @@
+extern crate gl;
+extern crate cgmath;
+
+use std::mem;
+use cgmath::{Point,Vector,Vector3,EuclideanVector};
+
+use glutil;
+use glutil::reload;
+use glutil::state;
+use material;
+use mesh;
+use game;
+use control;
+use shadow;
+
+/// A flat square behind or below the scene that receives the shadows of other objects
+pub struct Backdrop {
+    center: Vector3<f32>,
+    /// the side that is lit and visible
+    normal: Vector3<f32>,
+    /// length of a side
+    size: f32,
+    pub material: material::Material,
+    resource: Option<GlResource>,
+}
+
+struct GlResource {
+    program: reload::ReloadableProgram,
+    mesh: mesh::Mesh<Vertex>,
+}
+
+#[repr(C)]
+struct Vertex {
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+}
+
+impl Vertex {
+    fn new(pos: Vector3<f32>, norm: Vector3<f32>) -> Vertex {
+        Vertex {
+            position : pos,
+            normal : norm,
+        }
+    }
+}
+
+impl mesh::VertexLayout for Vertex {
+    fn attributes() -> Vec<mesh::Attribute> {
+        // locations taken from layout location in vertex shader
+        let float_size = mem::size_of::<f32>();
+        vec![
+            mesh::Attribute::new(0, 3, 0),
+            mesh::Attribute::new(1, 3, float_size * 3), // normal comes after position
+        ]
+    }
+}
+
+impl Backdrop {
+    pub fn new(center: Vector3<f32>, normal: Vector3<f32>, size: f32) -> Backdrop {
+        Backdrop {
+            center: center,
+            normal: normal.normalize(),
+            size: size,
+            material: material::Material::matte_paper(),
+            resource: None,
+        }
+    }
+
+    /// A floor at height y facing up
+    pub fn ground(y: f32, size: f32) -> Backdrop {
+        Backdrop::new(Vector3::new(0.0, y, 0.0), Vector3::new(0.0, 1.0, 0.0), size)
+    }
+
+    /// A wall at depth z facing the default camera
+    pub fn wall(z: f32, size: f32) -> Backdrop {
+        Backdrop::new(Vector3::new(0.0, 0.0, z), Vector3::new(0.0, 0.0, 1.0), size)
+    }
+
+    pub fn init(&mut self, mode: &reload::SourceMode) -> Result<(), glutil::GlError> {
+        let prog = try!(reload::ReloadableProgram::new(mode,
+            reload::ShaderFile::new("backdrop/vertex.glsl", include_str!("vertex.glsl")),
+            reload::ShaderFile::new("backdrop/fragment.glsl", include_str!("fragment.glsl"))));
+        let m = try!(mesh::Mesh::new(&self.generate_mesh(), gl::TRIANGLES));
+        self.resource = Some(GlResource {
+            program : prog,
+            mesh : m,
+        });
+        Ok(())
+    }
+
+    fn generate_mesh(&self) -> mesh::IndexedMesh<Vertex> {
+        // two edges spanning the plane, ordered so the quad is counter-clockwise seen from the normal
+        let n = self.normal;
+        let helper = if n.y.abs() < 0.99 { Vector3::new(0.0, 1.0, 0.0) } else { Vector3::new(0.0, 0.0, -1.0) };
+        let u = helper.cross(&n).normalize().mul_s(self.size * 0.5);
+        let v = n.cross(&u);
+        let c = self.center;
+        let mut m = mesh::IndexedMesh::new();
+        let v0 = m.push_vertex(Vertex::new(c.sub_v(&u).sub_v(&v), n));
+        let v1 = m.push_vertex(Vertex::new(c.add_v(&u).sub_v(&v), n));
+        let v2 = m.push_vertex(Vertex::new(c.add_v(&u).add_v(&v), n));
+        let v3 = m.push_vertex(Vertex::new(c.sub_v(&u).add_v(&v), n));
+        m.push_quad(v0, v1, v2, v3);
+        m
+    }
+}
+
+impl game::Object for Backdrop {
+    fn update(&mut self, _cs: &control::State, _dt: f32) -> Result<(),glutil::GlError> {
+        match self.resource {
+            Some(ref mut r) => { r.program.reload_if_changed(); },
+            None => (),
+        }
+        Ok(())
+    }
+
+    fn draw(&self, ctx: &game::DrawContext) -> Result<(),glutil::GlError> {
+        let r = match self.resource {
+            Some(ref r) => r,
+            None => return Err(glutil::GlError::Uninitialized { object: "Backdrop" }),
+        };
+        let p = &r.program;
+        try!(self.material.bind(&**p));
+        try!(shadow::bind(ctx.shadows, &**p));
+        try!(p.set_uniform("camera_position", &ctx.camera.position.to_vec()));
+        try!(p.set_uniform("view_projection", &ctx.camera.view_projection()));
+
+        try!(ctx.apply(&state::RenderState::opaque()));
+        try!(r.program.bind());
+        try!(r.mesh.draw());
+        r.program.unbind();
+        Ok(())
+    }
+}
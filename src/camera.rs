@@ -0,0 +1,173 @@
+extern crate cgmath;
+
+use std::num::FloatMath;
+use std::f32::consts;
+use cgmath::{Matrix,Matrix3,Matrix4,Point,Point3,Vector,Vector3,EuclideanVector,deg};
+
+use control;
+
+pub enum Projection {
+    /// fovy is the vertical field of view in degrees
+    Perspective { fovy: f32 },
+    /// height is the visible height in world units. The width follows the aspect ratio
+    Orthographic { height: f32 },
+}
+
+pub struct Camera {
+    pub position: Point3<f32>,
+    pub target: Point3<f32>,
+    pub up: Vector3<f32>,
+    pub projection: Projection,
+    pub near: f32,
+    pub far: f32,
+    /// width / height of the viewport
+    aspect: f32,
+}
+
+impl Camera {
+    /// A perspective camera looking at the origin from +z
+    pub fn new() -> Camera {
+        Camera {
+            position: Point3::new(0.0, 0.0, 2.5),
+            target: Point3::new(0.0, 0.0, 0.0),
+            up: Vector3::new(0.0, 1.0, 0.0),
+            projection: Projection::Perspective { fovy: 45.0 },
+            near: 0.1,
+            far: 100.0,
+            aspect: 1.0,
+        }
+    }
+
+    /// Keep the aspect ratio of the projection in sync with the viewport size in pixels
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        if width > 0 && height > 0 {
+            self.aspect = width as f32 / height as f32;
+        }
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.aspect
+    }
+
+    pub fn view(&self) -> Matrix4<f32> {
+        Matrix4::look_at(&self.position, &self.target, &self.up)
+    }
+
+    pub fn projection(&self) -> Matrix4<f32> {
+        match self.projection {
+            Projection::Perspective { fovy } =>
+                cgmath::perspective(deg(fovy), self.aspect, self.near, self.far),
+            Projection::Orthographic { height } => {
+                let h = height * 0.5;
+                let w = h * self.aspect;
+                cgmath::ortho(-w, w, -h, h, self.near, self.far)
+            },
+        }
+    }
+
+    pub fn view_projection(&self) -> Matrix4<f32> {
+        self.projection().mul_m(&self.view())
+    }
+}
+
+/// Matrix that transforms normals the same way model transforms positions,
+/// which is the inverse transpose of its upper 3x3 part
+pub fn normal_matrix(model: &Matrix4<f32>) -> Matrix3<f32> {
+    let m = Matrix3::new(
+        model.x.x, model.x.y, model.x.z,
+        model.y.x, model.y.y, model.y.z,
+        model.z.x, model.z.y, model.z.z);
+    // a degenerate model collapses the object anyway, so any normal will do
+    m.invert().unwrap_or(m).transpose()
+}
+
+/// Orbits a camera around its target.
+/// Left drag rotates, right drag pans and the wheel zooms.
+/// Movement keeps going after the button is released and slows down by damping every 1/60 s,
+/// so it coasts the same way at any frame rate.
+pub struct OrbitController {
+    /// rotation around the up axis in radians
+    yaw: f32,
+    /// elevation above the target in radians
+    pitch: f32,
+    distance: f32,
+    /// velocities are per 1/60 s
+    yaw_velocity: f32,
+    pitch_velocity: f32,
+    pan_velocity: (f32, f32),
+    zoom_velocity: f32,
+    /// fraction of the velocity kept every 1/60 s
+    pub damping: f32,
+    /// radians per pixel dragged
+    pub rotate_speed: f32,
+    /// fraction of the distance per pixel dragged
+    pub pan_speed: f32,
+    /// fraction of the distance per wheel step
+    pub zoom_speed: f32,
+}
+
+impl OrbitController {
+    /// Start from where the camera currently is
+    pub fn new(camera: &Camera) -> OrbitController {
+        let offset = camera.position.sub_p(&camera.target);
+        let distance = offset.length();
+        // a camera sitting on its target has no direction, so start level instead of with a NaN pitch
+        let pitch = if distance > 0.0 { (offset.y / distance).max(-1.0).min(1.0).asin() } else { 0.0 };
+        OrbitController {
+            yaw: offset.x.atan2(offset.z),
+            pitch: pitch,
+            distance: distance,
+            yaw_velocity: 0.0,
+            pitch_velocity: 0.0,
+            pan_velocity: (0.0, 0.0),
+            zoom_velocity: 0.0,
+            damping: 0.85,
+            rotate_speed: 0.01,
+            pan_speed: 0.002,
+            zoom_speed: 0.1,
+        }
+    }
+
+    /// Move the camera by the input of this frame, dt seconds after the last one
+    pub fn update(&mut self, cs: &control::State, camera: &mut Camera, dt: f32) {
+        // the input of this frame is applied in full, so it is spread over the 1/60 s steps of the frame
+        let steps = (dt * 60.0).max(1e-3);
+        let (dx, dy) = cs.cursor_delta;
+        let (dx, dy) = (dx as f32 / steps, dy as f32 / steps);
+        if cs.rotating {
+            self.yaw_velocity = -dx * self.rotate_speed;
+            self.pitch_velocity = dy * self.rotate_speed;
+        }
+        if cs.panning {
+            self.pan_velocity = (-dx * self.pan_speed, dy * self.pan_speed);
+        }
+        if cs.wheel_delta != 0 {
+            self.zoom_velocity = -(cs.wheel_delta as f32) * self.zoom_speed / steps;
+        }
+
+        // stop just short of the poles where the up vector would flip
+        let max_pitch = consts::FRAC_PI_2 - 0.01;
+        self.yaw += self.yaw_velocity * steps;
+        self.pitch = (self.pitch + self.pitch_velocity * steps).max(-max_pitch).min(max_pitch);
+        self.distance = (self.distance * (self.zoom_velocity * steps).exp()).max(camera.near * 2.0);
+
+        let offset = Vector3::new(
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.cos()).mul_s(self.distance);
+        // pan in the plane facing the camera, scaled so the target follows the cursor
+        let forward = offset.mul_s(-1.0).normalize();
+        let right = forward.cross(&camera.up).normalize();
+        let up = right.cross(&forward);
+        let (px, py) = self.pan_velocity;
+        let pan = right.mul_s(px * steps * self.distance).add_v(&up.mul_s(py * steps * self.distance));
+        camera.target = camera.target.add_v(&pan);
+        camera.position = camera.target.add_v(&offset);
+
+        let damping = self.damping.powf(steps);
+        self.yaw_velocity *= damping;
+        self.pitch_velocity *= damping;
+        self.pan_velocity = (px * damping, py * damping);
+        self.zoom_velocity *= damping;
+    }
+}
@@ -1,8 +1,16 @@
-#version 430
+#include "common/header.glsl"
+#include "common/shading.glsl"
+
+in vec3 world_position;
+flat in vec3 world_normal;
+
+uniform vec3 environment_color;
+uniform vec3 camera_position;
 
-in vec4 color;
 out vec4 pix_color;
+
 void main() {
-    // pix_color = vec4(1.0, 0.0, 0.0, 1.0);
-    pix_color = color;
-}
\ No newline at end of file
+    vec3 n = normalize(world_normal);
+    vec3 v = normalize(camera_position - world_position);
+    pix_color = shade(world_position, n, v, environment_color);
+}
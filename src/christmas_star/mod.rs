@@ -1,144 +1,329 @@
 extern crate gl;
 extern crate cgmath;
 
-use gl::types::{GLuint,GLfloat,GLsizeiptr,GLboolean};
-use std::ptr;
 use std::mem;
-use cgmath::{Vector3,Vector4,EuclideanVector};
+use std::f32::consts;
+use std::num::FloatMath;
+use cgmath::{Matrix,Point,Vector,Vector3,Vector4,EuclideanVector};
 
+use camera;
 use glutil;
+use glutil::reload;
+use glutil::state;
+use material;
+use mesh;
+use transform;
 use game;
-use light::directional;
+use shadow;
 use control;
 
+/// How the surface of the star responds to light
+#[deriving(Clone, PartialEq, Eq)]
+pub enum Shading {
+    BlinnPhong,
+    /// physically based metallic/roughness shading with the GGX microfacet model
+    CookTorrance,
+}
+
 pub struct ChristmasStar {
     geometry: Geometry,
-    resource: GlResource,
-    directional: directional::Light,
+    /// placement of the star, rotating and scaling around the center of the geometry
+    transform: transform::Transform,
+    spin: Option<transform::Spin>,
+    render_state: state::RenderState,
+    material: material::Material,
+    shading: Shading,
+    /// constant light coming from every direction, added to the ambient light
+    environment: Option<cgmath::Vector3<f32>>,
+    resource: Option<GlResource>,
 }
 
 struct GlResource {
-    shader_program: GLuint,
-    vao: GLuint,
-    vbo: GLuint,
-    indice_num : i32,
-    directional_name : String,
+    program: reload::ReloadableProgram,
+    mesh: mesh::Mesh<Vertex>,
 }
 
-struct Geometry {
-    center : cgmath::Vector3<f32>,
-    left_canyon_offset : cgmath::Vector3<f32>,
-    right_canyon_offset : cgmath::Vector3<f32>,
-    long_spike_length  : f32,
-    short_spike_length : f32,
-    thickness : f32, 
+/// Shape of a star with an arbitrary number of points.
+/// The outline lies around the z = center.z plane and the center is raised by half of the thickness
+/// on both the front and the back, which gives each spike its faceted look.
+pub struct Geometry {
+    pub center : cgmath::Vector3<f32>,
+    /// number of spikes
+    pub points : uint,
+    /// distance from the center to the tip of a spike
+    pub outer_radius : f32,
+    /// distance from the center to a canyon between two spikes, relative to outer_radius
+    pub inner_ratio : f32,
+    /// length of every other spike relative to outer_radius. 1.0 makes all spikes the same length
+    pub alternate_ratio : f32,
+    /// angle of the first spike in radians. Points straight up when None
+    pub rotation : Option<f32>,
+    /// distance between the front and back peak of the center
+    pub thickness : f32,
+    /// height of the side walls along the outline. No walls are generated when 0.0.
+    /// Must be below thickness, see set_thickness
+    pub rim : f32,
+}
+
+impl Geometry {
+    pub fn new(points: uint, inner_ratio: f32, rotation: Option<f32>) -> Result<Geometry, String> {
+        if points < 3 {
+            return Err(format!("A star needs at least 3 points but {} was given", points));
+        }
+        if inner_ratio <= 0.0 || inner_ratio >= 1.0 {
+            return Err(format!("inner_ratio should be between 0 and 1 but {} was given", inner_ratio));
+        }
+        Ok(Geometry {
+            center : cgmath::Vector3::new(0.0,0.0,0.0),
+            points : points,
+            outer_radius : 0.8,
+            inner_ratio : inner_ratio,
+            alternate_ratio : 1.0,
+            rotation : rotation,
+            thickness : 0.1,
+            rim : 0.0,
+        })
+    }
+
+    /// Set the distance between the peaks and the height of the side walls.
+    /// The peaks must stay in front of the rim, or the facets would face into the star
+    pub fn set_thickness(&mut self, thickness: f32, rim: f32) -> Result<(), String> {
+        if thickness <= 0.0 {
+            return Err(format!("thickness should be above 0 but {} was given", thickness));
+        }
+        if rim < 0.0 || rim >= thickness {
+            return Err(format!("rim should be at least 0 and below the thickness {} but {} was given", thickness, rim));
+        }
+        self.thickness = thickness;
+        self.rim = rim;
+        Ok(())
+    }
+
+    /// Four long and four short spikes
+    pub fn classic() -> Geometry {
+        Geometry {
+            center : cgmath::Vector3::new(0.0,0.0,0.0),
+            points : 8,
+            outer_radius : 0.8,
+            inner_ratio : 0.14,
+            alternate_ratio : 0.53,
+            rotation : None,
+            thickness : 0.1,
+            rim : 0.02,
+        }
+    }
+
+    /// Outline of the star alternating between spike tips and canyons in counter-clockwise order
+    fn outline(&self) -> Vec<cgmath::Vector3<f32>> {
+        let c = self.center;
+        let start = self.rotation.unwrap_or(consts::FRAC_PI_2);
+        let rad_per_point = consts::PI_2 / self.points as f32;
+        let inner_radius = self.outer_radius * self.inner_ratio;
+        let mut outline = Vec::with_capacity(self.points * 2);
+        for i in range(0, self.points) {
+            let tip_rad = start + rad_per_point * i as f32;
+            let tip_radius = if i % 2 == 1 {
+                self.outer_radius * self.alternate_ratio
+            } else {
+                self.outer_radius
+            };
+            let canyon_rad = tip_rad + rad_per_point * 0.5;
+            outline.push(cgmath::Vector3::new(
+                c.x + tip_rad.cos() * tip_radius,
+                c.y + tip_rad.sin() * tip_radius,
+                c.z));
+            outline.push(cgmath::Vector3::new(
+                c.x + canyon_rad.cos() * inner_radius,
+                c.y + canyon_rad.sin() * inner_radius,
+                c.z));
+        }
+        outline
+    }
 }
 
-struct Vertex {
-    position: cgmath::Vector3<f32>,
-    normal: cgmath::Vector3<f32>,
-    diffuse_color: cgmath::Vector4<f32>,
+/// A corner of the star. The normal is only used where the vertex is the last one of a triangle,
+/// since the shaders take the flat normal of a facet from its provoking vertex
+#[repr(C)]
+pub struct Vertex {
+    pub position: cgmath::Vector3<f32>,
+    pub normal: cgmath::Vector3<f32>,
 }
 
 impl Vertex {
-    fn new(pos: cgmath::Vector3<f32>, norm: cgmath::Vector3<f32>, diffuse: cgmath::Vector4<f32>) -> Vertex {
+    fn new(pos: cgmath::Vector3<f32>, norm: cgmath::Vector3<f32>) -> Vertex {
         Vertex {
             position : pos,
             normal : norm,
-            diffuse_color : diffuse,
         }
     }
 }
 
+impl mesh::VertexLayout for Vertex {
+    fn attributes() -> Vec<mesh::Attribute> {
+        // locations taken from layout location in vertex shader
+        let float_size = mem::size_of::<f32>();
+        vec![
+            mesh::Attribute::new(0, 3, 0),
+            mesh::Attribute::new(1, 3, float_size * 3), // normal comes after position
+        ]
+    }
+}
+
 impl ChristmasStar {
     pub fn new() -> ChristmasStar {
+        ChristmasStar::with_geometry(Geometry::classic())
+    }
+
+    pub fn with_geometry(geometry: Geometry) -> ChristmasStar {
         ChristmasStar{
-            geometry : Geometry {
-                center : cgmath::Vector3::new(0.0,0.0,0.0),
-                left_canyon_offset : cgmath::Vector3::new(0.05, 0.1, 0.0),
-                right_canyon_offset : cgmath::Vector3::new(0.1, 0.05, 0.0),
-                long_spike_length : 0.8,
-                short_spike_length : 0.3,
-                thickness : 0.1,
-            },
-            resource : GlResource {
-                shader_program : 0,
-                vao: 0,
-                vbo: 0,
-                indice_num: 0,
-                directional_name: "direction_to_light".to_string(),
-            },
-            directional : directional::Light::new(cgmath::Vector3::new(0.4, 0.5, 1.0)),
+            geometry : geometry,
+            transform : transform::Transform::identity(),
+            spin : None,
+            render_state : state::RenderState::opaque(),
+            material : material::Material::gold(),
+            shading : Shading::BlinnPhong,
+            environment : None,
+            resource : None,
         }
     }
 
-    pub fn init(&mut self) -> Result<(), String> {
-        let vss = include_str!("vertex.glsl");
-        let fss = include_str!("fragment.glsl");
-        let vs = try!(glutil::compile_shader(vss.as_slice(), gl::VERTEX_SHADER));
-        let fs = try!(glutil::compile_shader(fss.as_slice(), gl::FRAGMENT_SHADER));
-        let prog = try!(glutil::link_program(vs, fs));
+    pub fn transform(&self) -> &transform::Transform {
+        &self.transform
+    }
 
-        // remove shaders since we've finished linking it
-        glutil::remove_shader(prog, vs);
-        glutil::remove_shader(prog, fs);
- 
-        let (vao, vbo, ind_num) = try!(init_buffers(&self.geometry));
+    pub fn transform_mut(&mut self) -> &mut transform::Transform {
+        &mut self.transform
+    }
 
-        let r = &mut self.resource;
-        r.shader_program = prog;
-        r.vao = vao;
-        r.vbo = vbo;
-        r.indice_num = ind_num;
+    /// Keep rotating the star, or stop it with None
+    pub fn set_spin(&mut self, spin: Option<transform::Spin>) {
+        self.spin = spin;
+    }
 
-        try!(self.directional.init());
+    pub fn material(&self) -> &material::Material {
+        &self.material
+    }
 
-        Ok(())
+    /// Change the finish of the star. The geometry is kept as is
+    pub fn set_material(&mut self, m: material::Material) {
+        // translucent surfaces blend over what is behind them, so they must not hide it
+        if m.is_translucent() {
+            self.render_state.blend = Some(state::Blend::Alpha);
+            self.render_state.depth_write = false;
+        } else {
+            self.render_state.blend = None;
+            self.render_state.depth_write = true;
+        }
+        self.material = m;
     }
 
-    pub fn close(&mut self) {
-        self.directional.close();
+    /// Select the shading model. It is compiled into the shaders, so it must be set before init
+    pub fn set_shading(&mut self, shading: Shading) {
+        self.shading = shading;
+    }
 
-        let r = &mut self.resource;
-        unsafe {
-            gl::DeleteBuffers(1, &r.vbo);
-            gl::DeleteVertexArrays(1, &r.vao);
-        }
-        glutil::remove_program(r.shader_program);
-        r.shader_program = 0;
-        r.vbo = 0;
-        r.vao = 0;
+    pub fn shading(&self) -> Shading {
+        self.shading.clone()
+    }
+
+    pub fn set_environment(&mut self, color: Option<cgmath::Vector3<f32>>) {
+        self.environment = color;
+    }
+
+    pub fn environment(&self) -> Option<cgmath::Vector3<f32>> {
+        self.environment
+    }
+
+    /// The triangle list the star is drawn with, in model space
+    pub fn mesh(&self) -> mesh::IndexedMesh<Vertex> {
+        generate_mesh(&self.geometry)
+    }
+
+    /// Fixed function state the star is drawn with, which follows the material and set_cull
+    pub fn render_state(&self) -> &state::RenderState {
+        &self.render_state
+    }
+
+    /// Faces to discard. Back faces are culled by default since the star is a closed solid
+    pub fn set_cull(&mut self, cull: Option<state::Cull>) {
+        self.render_state.cull = cull;
+    }
+
+    pub fn model_matrix(&self) -> cgmath::Matrix4<f32> {
+        let c = self.geometry.center;
+        let to_center = cgmath::Matrix4::from_translation(&c);
+        let from_center = cgmath::Matrix4::from_translation(&c.mul_s(-1.0));
+        to_center.mul_m(&self.transform.matrix()).mul_m(&from_center)
+    }
+
+    pub fn init(&mut self, mode: &reload::SourceMode) -> Result<(), glutil::GlError> {
+        let defines = match self.shading {
+            Shading::BlinnPhong => Vec::new(),
+            Shading::CookTorrance => vec![("SHADING_COOK_TORRANCE".to_string(), "1".to_string())],
+        };
+        let prog = try!(reload::ReloadableProgram::with_defines(mode,
+            reload::ShaderFile::new("christmas_star/vertex.glsl", include_str!("vertex.glsl")),
+            reload::ShaderFile::new("christmas_star/fragment.glsl", include_str!("fragment.glsl")),
+            defines));
+        let m = try!(mesh::Mesh::new(&generate_mesh(&self.geometry), gl::TRIANGLES));
+
+        self.resource = Some(GlResource {
+            program : prog,
+            mesh : m,
+        });
+
+        Ok(())
     }
 }
 
 impl game::Object for ChristmasStar {
-    fn update(&mut self, cs: &control::State) -> Result<(),String> {
-        try!(self.directional.update(cs));
+    fn update(&mut self, _cs: &control::State, dt: f32) -> Result<(),glutil::GlError> {
+        match self.resource {
+            Some(ref mut r) => { r.program.reload_if_changed(); },
+            None => (),
+        }
+        match self.spin {
+            Some(ref s) => s.apply(&mut self.transform, dt),
+            None => (),
+        }
         Ok(())
     }
 
-    fn draw(&self) -> Result<(),String> {
-        let r = &self.resource;
-        unsafe {
-            gl::UseProgram(r.shader_program);
-            try!(glutil::check_error());
-
-            // update light position
-            let cstr = r.directional_name.to_c_str();
-            let loc = gl::GetUniformLocation(r.shader_program, cstr.as_ptr());
-            try!(glutil::check_error());
-            let vec_to_light = self.directional.vector_from(&self.geometry.center);
-            gl::Uniform3f(loc, vec_to_light.x, vec_to_light.y, vec_to_light.z);
-            try!(glutil::check_error());
-
-            gl::BindVertexArray(r.vao);
-            try!(glutil::check_error());
-            gl::DrawArrays(gl::TRIANGLES, 0, r.indice_num);
-            try!(glutil::check_error());
-            gl::BindVertexArray(0);
-            gl::UseProgram(0);
-        }
-        try!(self.directional.draw());
+    fn draw(&self, ctx: &game::DrawContext) -> Result<(),glutil::GlError> {
+        let r = match self.resource {
+            Some(ref r) => r,
+            None => return Err(glutil::GlError::Uninitialized { object: "ChristmasStar" }),
+        };
+        let model = self.model_matrix();
+        let p = &r.program;
+        let environment = self.environment.unwrap_or(cgmath::Vector3::new(0.0, 0.0, 0.0));
+        try!(p.set_uniform("environment_color", &environment));
+        try!(self.material.bind(&**p));
+        try!(shadow::bind(ctx.shadows, &**p));
+        try!(p.set_uniform("camera_position", &ctx.camera.position.to_vec()));
+        try!(p.set_uniform("model", &model));
+        let mvp = ctx.camera.view_projection().mul_m(&model);
+        try!(p.set_uniform("mvp", &mvp));
+        try!(p.set_uniform("normal_matrix", &camera::normal_matrix(&model)));
+
+        try!(ctx.apply(&self.render_state));
+        try!(r.program.bind());
+        try!(r.mesh.draw());
+        r.program.unbind();
+        Ok(())
+    }
+
+    fn draw_depth(&self, ctx: &game::DepthContext) -> Result<(),glutil::GlError> {
+        let r = match self.resource {
+            Some(ref r) => r,
+            None => return Err(glutil::GlError::Uninitialized { object: "ChristmasStar" }),
+        };
+        try!(ctx.program.set_uniform("mvp", &ctx.view_projection.mul_m(&self.model_matrix())));
+        // a translucent star still casts a shadow, so depth is always written here
+        try!(ctx.apply(&state::RenderState { cull: self.render_state.cull.clone(), ..state::RenderState::opaque() }));
+        try!(ctx.program.bind());
+        try!(r.mesh.draw());
+        ctx.program.unbind();
         Ok(())
     }
 }
@@ -152,160 +337,138 @@ fn calculate_normal(
     e0.cross(&e1).normalize()
 }
 
-fn add_partial_vertices(
-    center: cgmath::Vector3<f32>,
-    left_canyon_offset: cgmath::Vector3<f32>,
-    right_canyon_offset: cgmath::Vector3<f32>,
-    left_long_spike: cgmath::Vector3<f32>,
-    right_long_spike: cgmath::Vector3<f32>,
-    short_spike: cgmath::Vector3<f32>,
-    depth: f32,
-    vertices: &mut Vec<Vertex>) {
-    let lcox = left_canyon_offset.x;
-    let lcoy = left_canyon_offset.y;
-    let rcox = right_canyon_offset.x;
-    let rcoy = right_canyon_offset.y;
-    let cx = center.x;
-    let cy = center.y;
-    let cz = center.z;
-    let llsx = left_long_spike.x;
-    let llsy = left_long_spike.y;
-    let rlsx = right_long_spike.x;
-    let rlsy = right_long_spike.y;
-    let ssx = short_spike.x;
-    let ssy = short_spike.y;
-    let top = cz+depth;
-    let c = cgmath::Vector3::new(cx, cy, top);
-    let lc = cgmath::Vector3::new(cx+lcox, cy+lcoy, cz);
-    let rc = cgmath::Vector3::new(cx+rcox, cy+rcoy, cz);
-    let ss = cgmath::Vector3::new(cx+ssx, cy+ssy, cz);
-    let ll = cgmath::Vector3::new(cx+llsx, cy+llsy, cz);
-    let rl = cgmath::Vector3::new(cx+rlsx, cy+rlsy, cz);
-    let n0 = calculate_normal(&c, &lc, &ll);
-    // println!("n0: {}", n0);
-    let diffuse = cgmath::Vector4::new(0.9,0.9,0.0,1.0);
-    vertices.push(Vertex::new(c, n0, diffuse));
-    vertices.push(Vertex::new(ll, n0, diffuse));
-    vertices.push(Vertex::new(lc, n0, diffuse));
-
-    let n1 = calculate_normal(&c, &ss, &lc);
-    // println!("n1: {}", n1);
-    vertices.push(Vertex::new(c, n1, diffuse));
-    vertices.push(Vertex::new(lc, n1, diffuse));
-    vertices.push(Vertex::new(ss, n1, diffuse));
-
-    let n2 = calculate_normal(&c, &rc, &ss);
-    // println!("n2: {}", n2);
-    vertices.push(Vertex::new(c, n2, diffuse));
-    vertices.push(Vertex::new(ss, n2, diffuse));
-    vertices.push(Vertex::new(rc, n2, diffuse));
-
-    let n3 = calculate_normal(&c, &rl, &rc);
-    // println!("n3: {}", n3);
-    vertices.push(Vertex::new(c, n3, diffuse));
-    vertices.push(Vertex::new(rc, n3, diffuse));
-    vertices.push(Vertex::new(rl, n3, diffuse));
-}
+fn generate_mesh(geom: &Geometry) -> mesh::IndexedMesh<Vertex> {
+    let center = geom.center;
+    // the fields can be set without set_thickness, so keep the peaks in front of the rim here too
+    let thickness = geom.thickness.max(1e-3);
+    let rim = geom.rim.max(0.0).min(thickness * 0.9);
+    let half_rim = rim * 0.5;
+    let front_peak = cgmath::Vector3::new(center.x, center.y, center.z + thickness * 0.5);
+    let back_peak = cgmath::Vector3::new(center.x, center.y, center.z - thickness * 0.5);
+    let front_offset = cgmath::Vector3::new(0.0, 0.0, half_rim);
+    let back_offset = cgmath::Vector3::new(0.0, 0.0, -half_rim);
+    let outline = geom.outline();
+    let n = outline.len();
+    let front: Vec<cgmath::Vector3<f32>> = outline.iter().map(|p| p.add_v(&front_offset)).collect();
+    let back: Vec<cgmath::Vector3<f32>> = outline.iter().map(|p| p.add_v(&back_offset)).collect();
 
-fn generate_vertices(geom: &Geometry) -> Vec<Vertex> {
-    let c = geom.center;
-    let ls = geom.long_spike_length;
-    let ss = geom.short_spike_length;
-    let lco = geom.left_canyon_offset;
-    let rco = geom.right_canyon_offset;
-    let depth = geom.thickness * 0.5;
-
-    // add a quarter of a star per each add function
-    let mut vertices : Vec<Vertex> = Vec::new();
-    // top right
-    add_partial_vertices(c,
-        lco,
-        rco, 
-        cgmath::Vector3::new(0.0,ls,0.0),
-        cgmath::Vector3::new(ls,0.0,0.0),
-        cgmath::Vector3::new(ss,ss,0.0),
-        depth,
-        &mut vertices);
-    // bottom right
-    add_partial_vertices(c,
-        cgmath::Vector3::new(rco.x, -rco.y, lco.z),
-        cgmath::Vector3::new(lco.x, -lco.y, rco.z),
-        cgmath::Vector3::new(ls,0.0,0.0),
-        cgmath::Vector3::new(0.0,-ls,0.0),
-        cgmath::Vector3::new(ss,-ss,0.0),
-        depth,
-        &mut vertices);
-    // bottom left
-    add_partial_vertices(c,
-        cgmath::Vector3::new(-lco.x, -lco.y, lco.z),
-        cgmath::Vector3::new(-rco.x, -rco.y, rco.z),
-        cgmath::Vector3::new(0.0,-ls,0.0),
-        cgmath::Vector3::new(-ls,0.0,0.0),
-        cgmath::Vector3::new(-ss,-ss,0.0),
-        depth,
-        &mut vertices);
-    // top left
-    add_partial_vertices(c,
-        cgmath::Vector3::new(-rco.x, rco.y, lco.z),
-        cgmath::Vector3::new(-lco.x, lco.y, rco.z),
-        cgmath::Vector3::new(-ls, 0.0, 0.0),
-        cgmath::Vector3::new(0.0, ls, 0.0),
-        cgmath::Vector3::new(-ss, ss, 0.0),
-        depth,
-        &mut vertices);
-    vertices
-}
+    // facets from the peaks to each edge of the outline, and a wall between the front and back edge.
+    // the normal is flat across a facet and taken from the last vertex of its triangle, the provoking
+    // vertex. the other corners only need their position, so the peaks and outline points are shared
+    // by all the facets meeting there, and each of them provokes exactly one triangle
+    let mut m = mesh::IndexedMesh::new();
+    let front_center = m.push_vertex(Vertex::new(front_peak, cgmath::Vector3::new(0.0, 0.0, 1.0)));
+    let back_center = m.push_vertex(Vertex::new(back_peak, cgmath::Vector3::new(0.0, 0.0, -1.0)));
+    let mut front_ring = Vec::with_capacity(n);
+    let mut back_ring = Vec::with_capacity(n);
+    for i in range(0, n) {
+        let prev = (i + n - 1) % n;
+        // front facets are counter-clockwise when seen from +z, and point i ends the facet before it
+        let front_normal = calculate_normal(&front_peak, &front[prev], &front[i]);
+        front_ring.push(m.push_vertex(Vertex::new(front[i], front_normal)));
+        // back facets are the mirror of the front facets so the winding is reversed, and point i ends
+        // the facet after it
+        let next = (i + 1) % n;
+        let back_normal = calculate_normal(&back_peak, &back[next], &back[i]);
+        back_ring.push(m.push_vertex(Vertex::new(back[i], back_normal)));
+    }
+    for i in range(0, n) {
+        let next = (i + 1) % n;
+        m.push_triangle(front_center, front_ring[i], front_ring[next]);
+        m.push_triangle(back_center, back_ring[next], back_ring[i]);
+    }
 
-fn init_buffers(geom : &Geometry) -> Result<(GLuint, GLuint, i32), String> {
-    let vertices = generate_vertices(geom);
-    let mut vao = 0;
-    let mut vbo = 0;
-    let mut indice_num;
-    unsafe {
-        // Create Vertex Array Object
-        gl::GenVertexArrays(1, &mut vao);
-        try!(glutil::check_error());
-        gl::BindVertexArray(vao);
-        try!(glutil::check_error());
-        // Create a Vertex Buffer Object and copy the vertex data to it
-        gl::GenBuffers(1, &mut vbo);
-        try!(glutil::check_error());
-        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-        try!(glutil::check_error());
-        let vertice_size = mem::size_of::<Vertex>();
-        let vertice_num = vertices.len();
-        let float_size = mem::size_of::<GLfloat>();
-        // println!("Vertex size: {}", vertice_size);
-        // println!("Vertex num: {}", vertice_num);
-        // println!("float size: {}", float_size);
-        gl::BufferData(gl::ARRAY_BUFFER,
-            (vertice_num * vertice_size) as GLsizeiptr,
-            mem::transmute(&vertices[0]), gl::STATIC_DRAW);
-        try!(glutil::check_error());
-
-        indice_num = vertice_num as i32;
-
-        // values taken from layout location in vertex shader
-        let pos_location = 0;
-        let norm_location = 1;
-        let diffuse_location = 2;
-        let stride = vertice_size as i32;
-        gl::EnableVertexAttribArray(pos_location);
-        try!(glutil::check_error());
-        gl::EnableVertexAttribArray(norm_location);
-        try!(glutil::check_error());
-        gl::EnableVertexAttribArray(diffuse_location);
-        try!(glutil::check_error());
-        gl::VertexAttribPointer(pos_location, 3, gl::FLOAT, gl::FALSE as GLboolean, stride, ptr::null());
-        try!(glutil::check_error());
-        let normal_offset = mem::transmute(float_size * 3);  // normal comes after position
-        gl::VertexAttribPointer(norm_location, 3, gl::FLOAT, gl::FALSE as GLboolean, stride, normal_offset);
-        try!(glutil::check_error());
-        let diffuse_offset = mem::transmute(float_size * (3+3)); // diffuse comes after position and normal
-        gl::VertexAttribPointer(diffuse_location, 4, gl::FLOAT, gl::FALSE as GLboolean, stride, diffuse_offset);
-        try!(glutil::check_error());
-        gl::BindVertexArray(0);
-    }
-    Ok((vao, vbo, indice_num))
+    if rim > 0.0 {
+        // each wall is split along the f0-b1 diagonal, so its triangles end at b1 and at f0.
+        // the normals face away from the center since the outline is counter-clockwise
+        let mut wall_front = Vec::with_capacity(n);
+        let mut wall_back = Vec::with_capacity(n);
+        for i in range(0, n) {
+            let prev = (i + n - 1) % n;
+            let next = (i + 1) % n;
+            let prev_normal = calculate_normal(&back[prev], &back[i], &front[i]);
+            let normal = calculate_normal(&back[i], &back[next], &front[next]);
+            wall_back.push(m.push_vertex(Vertex::new(back[i], prev_normal)));
+            wall_front.push(m.push_vertex(Vertex::new(front[i], normal)));
+        }
+        for i in range(0, n) {
+            let next = (i + 1) % n;
+            m.push_triangle(wall_front[i], wall_back[i], wall_back[next]);
+            m.push_triangle(wall_back[next], wall_front[next], wall_front[i]);
+        }
+    }
+    m
 }
 
+#[cfg(test)]
+mod test {
+    use cgmath::{Vector,Vector3,EuclideanVector};
+    use super::{Geometry,generate_mesh,calculate_normal};
+
+    /// Every triangle must be wound counter-clockwise from outside, with its normal facing away from the center
+    fn assert_outward(geom: &Geometry) {
+        let m = generate_mesh(geom);
+        for t in range(0, m.indices.len() / 3) {
+            let v: Vec<&super::Vertex> = range(0, 3u).map(|k| &m.vertices[m.indices.get(t * 3 + k) as uint]).collect();
+            let centroid = v[0].position.add_v(&v[1].position).add_v(&v[2].position).div_s(3.0);
+            let outward = centroid.sub_v(&geom.center);
+            let winding = calculate_normal(&v[0].position, &v[1].position, &v[2].position);
+            // the normal of the facet is the one of its provoking vertex
+            let normal = v[2].normal;
+            assert!(normal.dot(&outward) > 0.0, "triangle {} faces inwards", t);
+            assert!(normal.dot(&winding) > 0.99, "triangle {} is wound against its normal", t);
+        }
+    }
+
+    /// Each vertex provokes one triangle at most, and the peaks are shared
+    fn assert_shared(geom: &Geometry) {
+        let m = generate_mesh(geom);
+        let triangles = m.indices.len() / 3;
+        assert!(m.vertices.len() < 3 * triangles);
+        assert!(m.vertices.len() <= triangles + 2);
+        let mut provoking: Vec<u32> = range(0, triangles).map(|t| m.indices.get(t * 3 + 2)).collect();
+        provoking.sort();
+        provoking.dedup();
+        assert_eq!(provoking.len(), triangles);
+    }
+
+    #[test]
+    fn classic_faces_outwards() {
+        assert_outward(&Geometry::classic());
+    }
+
+    #[test]
+    fn thick_rim_faces_outwards() {
+        let mut geom = Geometry::new(5, 0.4, None).unwrap();
+        geom.set_thickness(0.3, 0.2).unwrap();
+        geom.center = Vector3::new(0.5, -0.2, 1.0);
+        assert_outward(&geom);
+    }
+
+    #[test]
+    fn vertices_are_shared() {
+        assert_shared(&Geometry::classic());
+        let mut geom = Geometry::new(12, 0.5, None).unwrap();
+        assert_shared(&geom);
+        geom.set_thickness(0.3, 0.2).unwrap();
+        assert_shared(&geom);
+    }
+
+    #[test]
+    fn invalid_thickness_is_clamped() {
+        let mut geom = Geometry::classic();
+        geom.rim = 0.5;
+        assert_outward(&geom);
+        geom.thickness = -0.1;
+        assert_outward(&geom);
+    }
+
+    #[test]
+    fn set_thickness_rejects_rim_above_thickness() {
+        let mut geom = Geometry::classic();
+        assert!(geom.set_thickness(0.1, 0.1).is_err());
+        assert!(geom.set_thickness(-0.1, 0.0).is_err());
+        assert!(geom.set_thickness(0.1, -0.01).is_err());
+        assert!(geom.set_thickness(0.2, 0.05).is_ok());
+    }
+}
@@ -1,15 +1,19 @@
-#version 430
+#include "common/header.glsl"
 
 layout (location = 0) in vec3 position;
 layout (location = 1) in vec3 normal;
-layout (location = 2) in vec4 diffuse_color;
 
-uniform vec3 direction_to_light;
+uniform mat4 model;
+uniform mat4 mvp;
+uniform mat3 normal_matrix;
 
-out vec4 color;
+out vec3 world_position;
+// flat across each facet, from the last vertex of the triangle
+flat out vec3 world_normal;
 
 void main() {
-    float intensity = max(dot(normal, direction_to_light), 0.0);
-    color = intensity * diffuse_color;
-    gl_Position = vec4(position,1.0);
+    // lighting is done per fragment in world space
+    world_position = (model * vec4(position,1.0)).xyz;
+    world_normal = normal_matrix * normal;
+    gl_Position = mvp * vec4(position,1.0);
 }
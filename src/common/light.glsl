@@ -0,0 +1,43 @@
+// Mirrors light::Params
+struct Light {
+    // w is 0.0 for a directional light, where xyz is the direction towards the light
+    vec4 position;
+    vec3 color;
+    float intensity;
+    // constant, linear and quadratic factor of the attenuation over distance
+    vec3 attenuation;
+    vec3 ambient;
+    // a spotlight fades from spot_cos_inner to spot_cos_outer away from spot_direction.
+    // both are -1.0 for lights without a cone
+    vec3 spot_direction;
+    float spot_cos_inner;
+    float spot_cos_outer;
+};
+
+// Must match light::MAX_LIGHTS
+#define MAX_LIGHTS 8
+
+// Uploaded by light::LightList every frame
+layout (std140, binding = 0) uniform Lights {
+    int light_count;
+    Light lights[MAX_LIGHTS];
+};
+
+// Fraction of the light's color arriving at world_position, including its intensity,
+// along with the normalized direction the light comes from
+float incident_intensity(Light l, vec3 world_position, out vec3 direction_to_light) {
+    if (l.position.w == 0.0) {
+        direction_to_light = normalize(l.position.xyz);
+        return l.intensity;
+    }
+    vec3 to_light = l.position.xyz - world_position;
+    float d = length(to_light);
+    direction_to_light = to_light / max(d, 1e-4);
+    float attenuation = l.attenuation.x + l.attenuation.y * d + l.attenuation.z * d * d;
+    float cone = 1.0;
+    if (l.spot_cos_outer > -1.0) {
+        float cos_angle = dot(-direction_to_light, normalize(l.spot_direction));
+        cone = smoothstep(l.spot_cos_outer, max(l.spot_cos_inner, l.spot_cos_outer + 1e-4), cos_angle);
+    }
+    return l.intensity * cone / max(attenuation, 1e-4);
+}
@@ -0,0 +1,15 @@
+// Diffuse intensity of a surface facing normal, lit from direction_to_light
+float lambert(vec3 normal, vec3 direction_to_light) {
+    return max(dot(normal, direction_to_light), 0.0);
+}
+
+// Specular intensity using the half vector between the light and the viewer.
+// All vectors are expected to be normalized
+float blinn_phong_specular(vec3 normal, vec3 direction_to_light, vec3 direction_to_eye, float shininess) {
+    if (dot(normal, direction_to_light) <= 0.0) {
+        // no highlight on faces turned away from the light
+        return 0.0;
+    }
+    vec3 h = normalize(direction_to_light + direction_to_eye);
+    return pow(max(dot(normal, h), 0.0), shininess);
+}
@@ -0,0 +1,11 @@
+// Mirrors material::Material
+struct Material {
+    vec4 base_color;
+    vec3 specular;
+    float shininess;
+    float metallic;
+    float roughness;
+    vec3 emissive;
+};
+
+uniform Material material;
@@ -0,0 +1,53 @@
+// Cook-Torrance microfacet BRDF with the GGX distribution, Smith-Schlick geometry term
+// and the Fresnel-Schlick approximation. All vectors are expected to be normalized
+
+const float PI = 3.14159265359;
+
+float distribution_ggx(float n_dot_h, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+float geometry_schlick_ggx(float n_dot_x, float roughness) {
+    // remapping for direct lighting
+    float r = roughness + 1.0;
+    float k = r * r / 8.0;
+    return n_dot_x / (n_dot_x * (1.0 - k) + k);
+}
+
+vec3 fresnel_schlick(float cos_theta, vec3 f0) {
+    return f0 + (1.0 - f0) * pow(1.0 - cos_theta, 5.0);
+}
+
+// Reflectance at normal incidence. Dielectrics reflect about 4%, metals tint it with their albedo
+vec3 base_reflectance(vec3 albedo, float metallic) {
+    return mix(vec3(0.04), albedo, metallic);
+}
+
+// Outgoing radiance towards v from a light of the given radiance coming from l
+vec3 cook_torrance(vec3 n, vec3 l, vec3 v, vec3 albedo, float metallic, float roughness, vec3 radiance) {
+    float n_dot_l = max(dot(n, l), 0.0);
+    if (n_dot_l <= 0.0) {
+        return vec3(0.0);
+    }
+    float n_dot_v = max(dot(n, v), 1e-4);
+    vec3 h = normalize(l + v);
+    // a perfectly smooth surface makes the highlight infinitely small
+    float r = max(roughness, 0.04);
+    vec3 f = fresnel_schlick(max(dot(h, v), 0.0), base_reflectance(albedo, metallic));
+    float d = distribution_ggx(max(dot(n, h), 0.0), r);
+    float g = geometry_schlick_ggx(n_dot_v, r) * geometry_schlick_ggx(n_dot_l, r);
+    vec3 specular = d * g * f / (4.0 * n_dot_v * n_dot_l);
+    // energy that isn't reflected is refracted, and metals absorb all of it
+    vec3 kd = (vec3(1.0) - f) * (1.0 - metallic);
+    return (kd * albedo / PI + specular) * radiance * n_dot_l;
+}
+
+// Light from a constant colored environment, approximated without integrating over it
+vec3 constant_environment(vec3 n, vec3 v, vec3 albedo, float metallic, vec3 environment) {
+    vec3 f = fresnel_schlick(max(dot(n, v), 0.0), base_reflectance(albedo, metallic));
+    vec3 kd = (vec3(1.0) - f) * (1.0 - metallic);
+    return (kd * albedo + f) * environment;
+}
@@ -0,0 +1,44 @@
+// Surface shading with the material and every light of the scene, shared by lit objects.
+// Define SHADING_COOK_TORRANCE for physically based shading instead of Blinn-Phong
+#include "common/color.glsl"
+#include "common/lighting.glsl"
+#include "common/light.glsl"
+#include "common/shadow.glsl"
+#include "common/pbr.glsl"
+#include "common/material.glsl"
+
+// Color of the surface at world_position facing normal n, seen from direction v.
+// environment is constant light added to the ambient light of the lights
+vec4 shade(vec3 world_position, vec3 n, vec3 v, vec3 environment) {
+    vec3 ambient = environment;
+    for (int i = 0; i < light_count; ++i) {
+        ambient += lights[i].ambient;
+    }
+#ifdef SHADING_COOK_TORRANCE
+    // colors are authored in sRGB, so lighting is summed after converting them to linear
+    vec3 albedo = srgb_to_linear(material.base_color.rgb);
+    vec3 direct = vec3(0.0);
+    for (int i = 0; i < light_count; ++i) {
+        vec3 l;
+        float intensity = incident_intensity(lights[i], world_position, l);
+        intensity *= shadow_visibility(i, world_position, n, l);
+        vec3 radiance = srgb_to_linear(lights[i].color) * intensity;
+        // the BRDF divides the diffuse term by PI, so scale it back for a light of the same brightness
+        direct += cook_torrance(n, l, v, albedo, material.metallic, material.roughness, radiance * PI);
+    }
+    vec3 indirect = constant_environment(n, v, albedo, material.metallic, srgb_to_linear(ambient));
+    vec3 color = linear_to_srgb(direct + indirect + srgb_to_linear(material.emissive));
+#else
+    vec3 base = material.base_color.rgb;
+    vec3 color = ambient * base + material.emissive;
+    for (int i = 0; i < light_count; ++i) {
+        vec3 l;
+        float intensity = incident_intensity(lights[i], world_position, l);
+        intensity *= shadow_visibility(i, world_position, n, l);
+        vec3 radiance = lights[i].color * intensity;
+        color += lambert(n, l) * base * radiance;
+        color += blinn_phong_specular(n, l, v, material.shininess) * material.specular * radiance;
+    }
+#endif
+    return vec4(color, material.base_color.a);
+}
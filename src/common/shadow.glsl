@@ -0,0 +1,40 @@
+// Shadow maps rendered by shadow::ShadowMaps. Include after common/light.glsl
+
+// Must match shadow::MAX_SHADOWS
+#define MAX_SHADOWS 4
+
+uniform sampler2DArrayShadow shadow_maps;
+// from world space to the clip space of the light rendering each layer
+uniform mat4 shadow_matrices[MAX_SHADOWS];
+// layer of shadow_maps for each light, or -1 when the light casts no shadow
+uniform int shadow_layers[MAX_LIGHTS];
+uniform float shadow_constant_bias;
+// extra bias for surfaces at a grazing angle to the light, where acne shows up first
+uniform float shadow_slope_bias;
+// percentage closer filtering averages (2 * radius + 1)^2 lookups
+uniform int shadow_pcf_radius;
+
+// Fraction of the light of lights[light_index] that reaches world_position, from 0.0 to 1.0
+float shadow_visibility(int light_index, vec3 world_position, vec3 normal, vec3 direction_to_light) {
+    int layer = shadow_layers[light_index];
+    if (layer < 0) {
+        return 1.0;
+    }
+    vec4 clip = shadow_matrices[layer] * vec4(world_position, 1.0);
+    vec3 coord = clip.xyz / clip.w * 0.5 + 0.5;
+    if (coord.z > 1.0) {
+        // beyond the far plane of the light
+        return 1.0;
+    }
+    float bias = max(shadow_slope_bias * (1.0 - dot(normal, direction_to_light)), shadow_constant_bias);
+    vec2 texel = 1.0 / vec2(textureSize(shadow_maps, 0).xy);
+    float lit = 0.0;
+    for (int y = -shadow_pcf_radius; y <= shadow_pcf_radius; ++y) {
+        for (int x = -shadow_pcf_radius; x <= shadow_pcf_radius; ++x) {
+            vec2 uv = coord.xy + vec2(x, y) * texel;
+            lit += texture(shadow_maps, vec4(uv, float(layer), coord.z - bias));
+        }
+    }
+    float width = float(shadow_pcf_radius * 2 + 1);
+    return lit / (width * width);
+}
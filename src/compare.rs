@@ -0,0 +1,193 @@
+use std::cmp;
+use std::num::{Float,FloatMath};
+
+use offscreen::Image;
+
+/// How far a rendered image may be from its reference
+pub struct Tolerance {
+    /// difference of a color channel from 0 to 255 that is always accepted,
+    /// for rounding that differs between drivers
+    pub channel: u8,
+    /// CIE76 color difference that is accepted on top of it. 2.3 is about the smallest a person notices
+    pub perceptual: f32,
+    /// fraction of pixels that may exceed both, for edges that rasterize a bit differently
+    pub outliers: f32,
+}
+
+impl Tolerance {
+    pub fn new() -> Tolerance {
+        Tolerance {
+            channel: 2,
+            perceptual: 2.3,
+            outliers: 0.001,
+        }
+    }
+}
+
+/// Result of comparing two images of the same size
+pub struct Comparison {
+    /// pixels that exceeded both the channel and the perceptual tolerance
+    pub mismatched: uint,
+    pub total: uint,
+    /// largest difference of a single channel
+    pub max_channel: u8,
+    /// largest color difference in CIE76 units
+    pub max_perceptual: f32,
+    /// the reference dimmed to gray with mismatched pixels in red
+    pub diff: Image,
+    passed: bool,
+}
+
+impl Comparison {
+    pub fn passed(&self) -> bool {
+        self.passed
+    }
+}
+
+/// Compare actual against expected. Fails right away when the sizes differ
+pub fn compare(actual: &Image, expected: &Image, tolerance: &Tolerance) -> Result<Comparison, String> {
+    if actual.width != expected.width || actual.height != expected.height {
+        return Err(format!("size is {}x{} but the reference is {}x{}",
+            actual.width, actual.height, expected.width, expected.height));
+    }
+    let mut diff = Image::new(expected.width, expected.height);
+    let mut mismatched = 0u;
+    let mut max_channel = 0u8;
+    let mut max_perceptual = 0.0f32;
+    let pixels = actual.pixels.chunks(4).zip(expected.pixels.chunks(4));
+    for (i, (a, e)) in pixels.enumerate() {
+        let channel = range(0, 4).map(|c| abs_diff(a[c], e[c])).max().unwrap_or(0);
+        let perceptual = delta_e(a, e);
+        max_channel = cmp::max(max_channel, channel);
+        max_perceptual = max_perceptual.max(perceptual);
+        let bad = channel > tolerance.channel && perceptual > tolerance.perceptual;
+        let out = if bad {
+            mismatched += 1;
+            [255, 0, 0, 255]
+        } else {
+            // a faint copy of the reference shows where the mismatches are
+            let gray = 255 - ((255 - luma(e)) as u32 / 4) as u8;
+            [gray, gray, gray, 255]
+        };
+        for c in range(0, 4) {
+            diff.pixels[i * 4 + c] = out[c];
+        }
+    }
+    let total = (expected.width * expected.height) as uint;
+    Ok(Comparison {
+        mismatched: mismatched,
+        total: total,
+        max_channel: max_channel,
+        max_perceptual: max_perceptual,
+        diff: diff,
+        passed: mismatched as f32 <= tolerance.outliers * total as f32,
+    })
+}
+
+fn abs_diff(a: u8, b: u8) -> u8 {
+    if a > b { a - b } else { b - a }
+}
+
+fn luma(p: &[u8]) -> u8 {
+    (0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32) as u8
+}
+
+/// Distance between two sRGB colors in CIELAB space, after compositing them over white
+fn delta_e(a: &[u8], b: &[u8]) -> f32 {
+    let (l0, a0, b0) = lab(a);
+    let (l1, a1, b1) = lab(b);
+    ((l0 - l1).powi(2) + (a0 - a1).powi(2) + (b0 - b1).powi(2)).sqrt()
+}
+
+fn lab(p: &[u8]) -> (f32, f32, f32) {
+    let alpha = p[3] as f32 / 255.0;
+    let linear = |c: u8| {
+        let c = (c as f32 / 255.0) * alpha + (1.0 - alpha);
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    let (r, g, b) = (linear(p[0]), linear(p[1]), linear(p[2]));
+    // XYZ relative to the D65 white point
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.9505;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.089;
+    let f = |t: f32| if t > 0.008856 { t.cbrt() } else { 7.787 * t + 16.0 / 116.0 };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
+
+#[cfg(test)]
+mod test {
+    use offscreen::Image;
+    use super::{Tolerance,compare};
+
+    fn solid(width: u32, height: u32, color: [u8, ..4]) -> Image {
+        let mut image = Image::new(width, height);
+        for p in image.pixels.chunks_mut(4) {
+            for c in range(0, 4) {
+                p[c] = color[c];
+            }
+        }
+        image
+    }
+
+    fn set(image: &mut Image, x: u32, y: u32, color: [u8, ..4]) {
+        let i = ((y * image.width + x) * 4) as uint;
+        for c in range(0, 4) {
+            image.pixels[i + c] = color[c];
+        }
+    }
+
+    #[test]
+    fn same_image_passes() {
+        let image = solid(8, 8, [200, 30, 60, 255]);
+        let c = compare(&image, &image, &Tolerance::new()).unwrap();
+        assert!(c.passed());
+        assert_eq!(c.mismatched, 0);
+        assert_eq!(c.max_channel, 0);
+        assert_eq!(c.max_perceptual, 0.0);
+    }
+
+    #[test]
+    fn small_channel_change_passes() {
+        let expected = solid(8, 8, [200, 30, 60, 255]);
+        let mut actual = solid(8, 8, [200, 30, 60, 255]);
+        set(&mut actual, 3, 4, [200, 32, 60, 255]);
+        let c = compare(&actual, &expected, &Tolerance::new()).unwrap();
+        assert!(c.passed());
+        assert_eq!(c.mismatched, 0);
+        assert_eq!(c.max_channel, 2);
+    }
+
+    #[test]
+    fn large_change_fails_and_is_marked() {
+        let expected = solid(8, 8, [255, 255, 255, 255]);
+        let mut actual = solid(8, 8, [255, 255, 255, 255]);
+        set(&mut actual, 3, 4, [0, 0, 0, 255]);
+        let c = compare(&actual, &expected, &Tolerance::new()).unwrap();
+        assert!(!c.passed());
+        assert_eq!(c.mismatched, 1);
+        assert_eq!(c.max_channel, 255);
+        assert_eq!(c.diff.pixel(3, 4), [255, 0, 0, 255]);
+        assert!(c.diff.pixel(4, 4) != [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn outliers_up_to_the_fraction_pass() {
+        // 0.001 of 1000 pixels allows a single one
+        let expected = solid(100, 10, [255, 255, 255, 255]);
+        let mut actual = solid(100, 10, [255, 255, 255, 255]);
+        set(&mut actual, 10, 5, [0, 0, 0, 255]);
+        assert!(compare(&actual, &expected, &Tolerance::new()).unwrap().passed());
+        set(&mut actual, 90, 5, [0, 0, 0, 255]);
+        let c = compare(&actual, &expected, &Tolerance::new()).unwrap();
+        assert_eq!(c.mismatched, 2);
+        assert!(!c.passed());
+    }
+
+    #[test]
+    fn different_sizes_are_an_error() {
+        let a = solid(8, 8, [0, 0, 0, 255]);
+        let b = solid(8, 9, [0, 0, 0, 255]);
+        assert!(compare(&a, &b, &Tolerance::new()).is_err());
+    }
+}
@@ -7,6 +7,17 @@ pub struct State {
     pub move_down : bool,
     pub move_left : bool,
     pub move_right : bool,
+    pub rotating : bool,
+    pub panning : bool,
+    /// cursor movement in pixels since the last frame
+    pub cursor_delta : (i32, i32),
+    /// wheel movement since the last frame
+    pub wheel_delta : i32,
+    /// light chosen with the number keys during the frame, counting from 0
+    pub selected_light : Option<uint>,
+    /// a screenshot was requested during the frame
+    pub screenshot : bool,
+    cursor : Option<(i32, i32)>,
 }
 
 impl State {
@@ -16,6 +27,13 @@ impl State {
             move_down : false,
             move_left : false,
             move_right : false,
+            rotating : false,
+            panning : false,
+            cursor_delta : (0, 0),
+            wheel_delta : 0,
+            selected_light : None,
+            screenshot : false,
+            cursor : None,
         }
     }
 
@@ -31,17 +49,60 @@ impl State {
                 glutin::VirtualKeyCode::Right => self.move_right = pressed,
                 glutin::VirtualKeyCode::Up => self.move_up = pressed,
                 glutin::VirtualKeyCode::Down => self.move_down = pressed,
+                glutin::VirtualKeyCode::Key1 if pressed => self.selected_light = Some(0),
+                glutin::VirtualKeyCode::Key2 if pressed => self.selected_light = Some(1),
+                glutin::VirtualKeyCode::Key3 if pressed => self.selected_light = Some(2),
+                glutin::VirtualKeyCode::Key4 if pressed => self.selected_light = Some(3),
+                glutin::VirtualKeyCode::Key5 if pressed => self.selected_light = Some(4),
+                glutin::VirtualKeyCode::Key6 if pressed => self.selected_light = Some(5),
+                glutin::VirtualKeyCode::Key7 if pressed => self.selected_light = Some(6),
+                glutin::VirtualKeyCode::Key8 if pressed => self.selected_light = Some(7),
+                glutin::VirtualKeyCode::F12 if pressed => self.screenshot = true,
                 _ => (),
             },
             None => (),
         }
     }
+
+    pub fn handle_mouse_input(&mut self, elem_state: glutin::ElementState, button: glutin::MouseButton) {
+        let pressed = elem_state == glutin::ElementState::Pressed;
+        match button {
+            glutin::MouseButton::LeftMouseButton => self.rotating = pressed,
+            glutin::MouseButton::RightMouseButton => self.panning = pressed,
+            _ => (),
+        }
+    }
+
+    pub fn handle_mouse_moved(&mut self, position: (int, int)) {
+        let (x, y) = position;
+        let (x, y) = (x as i32, y as i32);
+        match self.cursor {
+            Some((px, py)) => {
+                let (dx, dy) = self.cursor_delta;
+                self.cursor_delta = (dx + x - px, dy + y - py);
+            },
+            None => (),
+        }
+        self.cursor = Some((x, y));
+    }
+
+    pub fn handle_mouse_wheel(&mut self, delta: i32) {
+        self.wheel_delta += delta;
+    }
+
+    /// Reset the movement accumulated during the frame
+    pub fn end_frame(&mut self) {
+        self.cursor_delta = (0, 0);
+        self.wheel_delta = 0;
+        self.selected_light = None;
+        self.screenshot = false;
+    }
 }
 
 impl fmt::Show for State {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "move_up: {}, move_down: {}, move_left: {}, move_right: {}",
-            self.move_up, self.move_down, self.move_left, self.move_right)
+        write!(f, "move_up: {}, move_down: {}, move_left: {}, move_right: {}, rotating: {}, panning: {}",
+            self.move_up, self.move_down, self.move_left, self.move_right, self.rotating, self.panning)
     }
 }
 
@@ -1,7 +1,49 @@
+extern crate cgmath;
+
+use std::cell::RefCell;
+
 use control;
+use camera;
+use glutil;
+use shadow;
+
+/// Per frame state shared by every object while drawing
+pub struct DrawContext<'a> {
+    pub camera: &'a camera::Camera,
+    /// objects declare the state they need here instead of changing it directly
+    pub state: &'a RefCell<glutil::state::StateTracker>,
+    /// shadow maps of the current frame. None when shadows are turned off
+    pub shadows: Option<&'a shadow::ShadowMaps>,
+}
+
+impl<'a> DrawContext<'a> {
+    pub fn apply(&self, state: &glutil::state::RenderState) -> Result<(), glutil::GlError> {
+        self.state.borrow_mut().apply(state)
+    }
+}
+
+/// State for drawing the depth of an object as seen from a light
+pub struct DepthContext<'a> {
+    /// from world space to the clip space of the light
+    pub view_projection: cgmath::Matrix4<f32>,
+    /// program that writes the depth. It takes the position at location 0 and the `mvp` uniform
+    pub program: &'a glutil::Program,
+    pub state: &'a RefCell<glutil::state::StateTracker>,
+}
+
+impl<'a> DepthContext<'a> {
+    pub fn apply(&self, state: &glutil::state::RenderState) -> Result<(), glutil::GlError> {
+        self.state.borrow_mut().apply(state)
+    }
+}
 
 /// An object within the game
 pub trait Object {
-    fn update(&mut self, cs: &control::State) -> Result<(),String>;
-    fn draw(&self) -> Result<(),String>;
+    /// dt is the time since the last update in seconds
+    fn update(&mut self, cs: &control::State, dt: f32) -> Result<(),glutil::GlError>;
+    fn draw(&self, ctx: &DrawContext) -> Result<(),glutil::GlError>;
+    /// Draw the geometry into a shadow map with ctx.program. Objects that cast no shadow do nothing
+    fn draw_depth(&self, _ctx: &DepthContext) -> Result<(),glutil::GlError> {
+        Ok(())
+    }
 }
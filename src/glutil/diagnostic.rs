@@ -0,0 +1,247 @@
+use std::fmt;
+use std::ascii::AsciiExt;
+
+#[deriving(Clone, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Show for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A single message from a shader compile log
+#[deriving(Clone)]
+pub struct Diagnostic {
+    pub file: String,
+    /// 1 based line number, when the driver reported one
+    pub line: Option<uint>,
+    /// 1 based column number, when the driver reported one
+    pub column: Option<uint>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Show for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{}", self.file));
+        match self.line {
+            Some(l) => try!(write!(f, ":{}", l)),
+            None => (),
+        }
+        match self.column {
+            Some(c) => try!(write!(f, ":{}", c)),
+            None => (),
+        }
+        write!(f, ": {}: {}", self.severity, self.message)
+    }
+}
+
+/// Parse a shader info log into diagnostics.
+/// Understands the following styles, where the first number is the source string index:
+///
+/// * Mesa: `0:12(3): error: message`
+/// * NVIDIA: `0(12) : error C0000: message`
+/// * AMD/Intel: `ERROR: 0:12: message`
+///
+/// Lines that don't match any of them are kept as diagnostics without a location.
+pub fn parse_log(log: &str, file: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for l in log.lines() {
+        let l = l.trim();
+        if l.is_empty() {
+            continue;
+        }
+        diagnostics.push(parse_line(l, file));
+    }
+    diagnostics
+}
+
+fn parse_line(l: &str, file: &str) -> Diagnostic {
+    // AMD style puts the severity in front of the location
+    let (prefix_severity, rest) = strip_severity(l);
+    let (line, column, rest) = match parse_location(rest) {
+        Some(loc) => loc,
+        None => (None, None, rest),
+    };
+    // Mesa and NVIDIA put the severity after the location
+    let (severity, message) = match prefix_severity {
+        Some(s) => (s, rest),
+        None => match strip_severity(rest) {
+            (Some(s), m) => (s, m),
+            (None, m) => (Severity::Error, m),
+        },
+    };
+    Diagnostic {
+        file: file.to_string(),
+        line: line,
+        column: column,
+        severity: severity,
+        message: message.trim().to_string(),
+    }
+}
+
+/// Strip a leading "error", "ERROR:" or "warning C1234:" and return what was found
+fn strip_severity(s: &str) -> (Option<Severity>, &str) {
+    let s = s.trim_left();
+    let lower = s.to_ascii_lower();
+    let severity = if lower.starts_with("error") {
+        Severity::Error
+    } else if lower.starts_with("warning") {
+        Severity::Warning
+    } else {
+        return (None, s);
+    };
+    // skip up to the colon so vendor codes such as "C0000" are dropped as well
+    match s.find(':') {
+        Some(i) => (Some(severity), s.slice_from(i + 1).trim_left()),
+        None => (Some(severity), ""),
+    }
+}
+
+/// Parse "0:12(3):", "0(12) :" or "0:12:" and return the line, column and the rest of the string
+fn parse_location(s: &str) -> Option<(Option<uint>, Option<uint>, &str)> {
+    let s = s.trim_left();
+    let (_, s) = match take_number(s) {
+        Some(r) => r,
+        None => return None,
+    };
+    let (line, column, s) = if s.starts_with("(") {
+        // NVIDIA: index(line)
+        let (line, s) = match take_number(s.slice_from(1)) {
+            Some(r) => r,
+            None => return None,
+        };
+        if !s.starts_with(")") {
+            return None;
+        }
+        (line, None, s.slice_from(1))
+    } else if s.starts_with(":") {
+        // Mesa or AMD: index:line with an optional (column)
+        let (line, s) = match take_number(s.slice_from(1)) {
+            Some(r) => r,
+            None => return None,
+        };
+        if s.starts_with("(") {
+            match take_number(s.slice_from(1)) {
+                Some((col, rest)) if rest.starts_with(")") => (line, Some(col), rest.slice_from(1)),
+                _ => return None,
+            }
+        } else {
+            (line, None, s)
+        }
+    } else {
+        return None;
+    };
+    let s = s.trim_left();
+    if !s.starts_with(":") {
+        return None;
+    }
+    Some((Some(line), column, s.slice_from(1)))
+}
+
+fn take_number(s: &str) -> Option<(uint, &str)> {
+    let len = s.chars().take_while(|c| c.is_digit(10)).count();
+    if len == 0 {
+        return None;
+    }
+    from_str::<uint>(s.slice_to(len)).map(|n| (n, s.slice_from(len)))
+}
+
+/// Render the diagnostics with the offending source line and a marker under the column.
+/// sources are pairs of file name and text, looked up by the file of each diagnostic
+pub fn render(diagnostics: &[Diagnostic], sources: &[(String, String)]) -> String {
+    let mut out = String::new();
+    for d in diagnostics.iter() {
+        out.push_str(d.to_string().as_slice());
+        out.push('\n');
+        let lines : Vec<&str> = match sources.iter().find(|&&(ref f, _)| *f == d.file) {
+            Some(&(_, ref text)) => text.as_slice().lines().collect(),
+            None => continue,
+        };
+        let line = match d.line {
+            Some(l) if l >= 1 && l <= lines.len() => l,
+            _ => continue,
+        };
+        let number = line.to_string();
+        let gutter = String::from_char(number.len(), ' ');
+        out.push_str(format!(" {} | {}\n", number, lines[line - 1]).as_slice());
+        match d.column {
+            Some(c) if c >= 1 => {
+                let pad = String::from_char(c - 1, ' ');
+                out.push_str(format!(" {} | {}^\n", gutter, pad).as_slice());
+            },
+            _ => (),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Diagnostic,Severity,parse_log,render};
+
+    fn parse_one(l: &str) -> Diagnostic {
+        let d = parse_log(l, "star.glsl");
+        assert_eq!(d.len(), 1);
+        d[0].clone()
+    }
+
+    #[test]
+    fn mesa() {
+        let d = parse_one("0:12(3): error: `foo' undeclared");
+        assert_eq!(d.line, Some(12));
+        assert_eq!(d.column, Some(3));
+        assert_eq!(d.severity, Severity::Error);
+        assert_eq!(d.message.as_slice(), "`foo' undeclared");
+    }
+
+    #[test]
+    fn nvidia() {
+        let d = parse_one("0(12) : error C0000: syntax error, unexpected '}'");
+        assert_eq!(d.line, Some(12));
+        assert_eq!(d.column, None);
+        assert_eq!(d.severity, Severity::Error);
+        assert_eq!(d.message.as_slice(), "syntax error, unexpected '}'");
+    }
+
+    #[test]
+    fn amd() {
+        let d = parse_one("WARNING: 0:12: implicit cast");
+        assert_eq!(d.line, Some(12));
+        assert_eq!(d.column, None);
+        assert_eq!(d.severity, Severity::Warning);
+        assert_eq!(d.message.as_slice(), "implicit cast");
+        assert_eq!(parse_one("ERROR: 0:12: 'foo' : undeclared identifier").severity, Severity::Error);
+    }
+
+    #[test]
+    fn no_location() {
+        let d = parse_log("\nLink failed because of missing stage\n\n", "star.glsl");
+        assert_eq!(d.len(), 1);
+        assert_eq!(d[0].line, None);
+        assert_eq!(d[0].column, None);
+        assert_eq!(d[0].severity, Severity::Error);
+        assert_eq!(d[0].message.as_slice(), "Link failed because of missing stage");
+    }
+
+    #[test]
+    fn render_shows_the_line() {
+        let sources = vec![("star.glsl".to_string(), "void main() {\n    foo = 1;\n}\n".to_string())];
+        let out = render(parse_log("0:2(5): error: `foo' undeclared\n0:40(1): error: past the end",
+            "star.glsl").as_slice(), sources.as_slice());
+        let lines: Vec<&str> = out.as_slice().lines().collect();
+        assert_eq!(lines, vec![
+            "star.glsl:2:5: error: `foo' undeclared",
+            " 2 |     foo = 1;",
+            "   |     ^",
+            "star.glsl:40:1: error: past the end",
+        ]);
+    }
+}
@@ -0,0 +1,127 @@
+extern crate gl;
+
+use gl::types::{GLenum,GLuint,GLint,GLsizei};
+
+use glutil::{GlError,check_error};
+use glutil::texture::Texture;
+
+/// A framebuffer object that is deleted when dropped
+pub struct Framebuffer {
+    id: GLuint,
+}
+
+impl Framebuffer {
+    pub fn new() -> Result<Framebuffer, GlError> {
+        let mut id = 0;
+        unsafe { gl::GenFramebuffers(1, &mut id); }
+        try!(check_error("glGenFramebuffers"));
+        if id == 0 {
+            return Err(GlError::Creation { resource: "framebuffer" });
+        }
+        Ok(Framebuffer { id: id })
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Draw into this framebuffer instead of the window
+    pub fn bind(&self) -> Result<(), GlError> {
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, self.id); }
+        check_error("glBindFramebuffer")
+    }
+
+    /// Go back to drawing into the window
+    pub fn unbind(&self) {
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0); }
+    }
+
+    /// Bind the framebuffer and attach one layer of an array texture to attachment
+    pub fn attach_layer(&self, attachment: GLenum, texture: &Texture, layer: GLint) -> Result<(), GlError> {
+        try!(self.bind());
+        unsafe { gl::FramebufferTextureLayer(gl::FRAMEBUFFER, attachment, texture.id(), 0, layer); }
+        check_error("glFramebufferTextureLayer")
+    }
+
+    /// Bind the framebuffer and attach a renderbuffer to attachment
+    pub fn attach_renderbuffer(&self, attachment: GLenum, renderbuffer: &Renderbuffer) -> Result<(), GlError> {
+        try!(self.bind());
+        unsafe {
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, attachment, gl::RENDERBUFFER, renderbuffer.id());
+        }
+        check_error("glFramebufferRenderbuffer")
+    }
+
+    /// Bind the framebuffer and declare that it has no color output, as for a depth-only pass
+    pub fn disable_color(&self) -> Result<(), GlError> {
+        try!(self.bind());
+        unsafe {
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+        }
+        check_error("glDrawBuffer")
+    }
+
+    /// Bind the framebuffer and check that it can be drawn into
+    pub fn check_status(&self) -> Result<(), GlError> {
+        try!(self.bind());
+        let status = unsafe { gl::CheckFramebufferStatus(gl::FRAMEBUFFER) };
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(GlError::Framebuffer { status: status });
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteFramebuffers(1, &self.id); }
+    }
+}
+
+/// The framebuffer currently drawn into, 0 for the window
+pub fn bound() -> GLuint {
+    let mut id = 0 as GLint;
+    unsafe { gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut id); }
+    id as GLuint
+}
+
+/// Draw into a framebuffer returned by bound again
+pub fn restore(id: GLuint) {
+    unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, id); }
+}
+
+/// Storage for one image of a framebuffer that is never sampled, deleted when dropped
+pub struct Renderbuffer {
+    id: GLuint,
+}
+
+impl Renderbuffer {
+    /// Allocate a width x height image of internal_format, such as RGBA8 or DEPTH_COMPONENT24
+    pub fn new(internal_format: GLenum, width: GLsizei, height: GLsizei) -> Result<Renderbuffer, GlError> {
+        let mut id = 0;
+        unsafe { gl::GenRenderbuffers(1, &mut id); }
+        try!(check_error("glGenRenderbuffers"));
+        if id == 0 {
+            return Err(GlError::Creation { resource: "renderbuffer" });
+        }
+        let renderbuffer = Renderbuffer { id: id };
+        unsafe {
+            gl::BindRenderbuffer(gl::RENDERBUFFER, id);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, internal_format, width, height);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+        }
+        try!(check_error("glRenderbufferStorage"));
+        Ok(renderbuffer)
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+}
+
+impl Drop for Renderbuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteRenderbuffers(1, &self.id); }
+    }
+}
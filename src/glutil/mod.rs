@@ -0,0 +1,441 @@
+extern crate gl;
+extern crate libc;
+
+use gl::types::{GLenum,GLuint,GLchar,GLint,GLintptr,GLsizei,GLsizeiptr};
+use std::ptr;
+use std::mem;
+use std::fmt;
+use std::error::Error;
+use std::io::{File,IoError,IoResult};
+use std::collections::HashMap;
+
+pub mod diagnostic;
+pub mod framebuffer;
+pub mod preprocess;
+pub mod reload;
+pub mod state;
+pub mod texture;
+pub mod uniform;
+
+/// Stage of the pipeline a shader is compiled for
+#[deriving(Clone, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Geometry,
+    Other(GLenum),
+}
+
+impl ShaderStage {
+    pub fn from_gl(ty: GLenum) -> ShaderStage {
+        match ty {
+            gl::VERTEX_SHADER => ShaderStage::Vertex,
+            gl::FRAGMENT_SHADER => ShaderStage::Fragment,
+            gl::GEOMETRY_SHADER => ShaderStage::Geometry,
+            _ => ShaderStage::Other(ty),
+        }
+    }
+}
+
+impl fmt::Show for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShaderStage::Vertex => write!(f, "vertex"),
+            ShaderStage::Fragment => write!(f, "fragment"),
+            ShaderStage::Geometry => write!(f, "geometry"),
+            ShaderStage::Other(ty) => write!(f, "shader type 0x{:x}", ty),
+        }
+    }
+}
+
+/// Errors reported by OpenGL or by the objects built on top of it
+pub enum GlError {
+    /// A shader failed to compile.
+    /// sources holds the name and text of each file so the diagnostics can be shown with them
+    Compile { stage: ShaderStage, file: String, sources: Vec<(String, String)>, diagnostics: Vec<diagnostic::Diagnostic> },
+    /// A shader could not be preprocessed
+    Preprocess { file: String, line: uint, message: String },
+    /// A program failed to link
+    Link { log: String },
+    /// glGetError returned code right after call_site
+    Call { code: GLenum, call_site: &'static str },
+    /// A GL object could not be created
+    Creation { resource: &'static str },
+    /// An object was used before its GL resources were created
+    Uninitialized { object: &'static str },
+    /// A shader source could not be read from disk
+    Source { file: String, error: IoError },
+    /// A uniform does not exist in the program, or does not match the value given to it
+    Uniform { name: String, reason: &'static str },
+    /// A framebuffer can't be drawn into, with status from glCheckFramebufferStatus
+    Framebuffer { status: GLenum },
+}
+
+impl GlError {
+    fn code_name(code: GLenum) -> &'static str {
+        match code {
+            gl::INVALID_ENUM => "invalid enum",
+            gl::INVALID_VALUE => "invalid value",
+            gl::INVALID_OPERATION => "invalid operation",
+            gl::INVALID_FRAMEBUFFER_OPERATION => "invalid framebuffer operation",
+            gl::OUT_OF_MEMORY => "out of memory",
+            gl::STACK_UNDERFLOW => "stack underflow",
+            gl::STACK_OVERFLOW => "stack overflow",
+            _ => "unknown error",
+        }
+    }
+
+    fn framebuffer_status_name(status: GLenum) -> &'static str {
+        match status {
+            gl::FRAMEBUFFER_UNDEFINED => "undefined",
+            gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => "incomplete attachment",
+            gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => "missing attachment",
+            gl::FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER => "incomplete draw buffer",
+            gl::FRAMEBUFFER_INCOMPLETE_READ_BUFFER => "incomplete read buffer",
+            gl::FRAMEBUFFER_UNSUPPORTED => "unsupported",
+            gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => "incomplete multisample",
+            gl::FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS => "incomplete layer targets",
+            _ => "unknown status",
+        }
+    }
+}
+
+impl Error for GlError {
+    fn description(&self) -> &str {
+        match *self {
+            GlError::Compile { .. } => "shader compile failed",
+            GlError::Preprocess { .. } => "shader preprocess failed",
+            GlError::Link { .. } => "program link failed",
+            GlError::Call { .. } => "OpenGL call failed",
+            GlError::Creation { .. } => "OpenGL object creation failed",
+            GlError::Uninitialized { .. } => "object used before init",
+            GlError::Source { .. } => "shader source could not be read",
+            GlError::Uniform { .. } => "uniform could not be set",
+            GlError::Framebuffer { .. } => "framebuffer is incomplete",
+        }
+    }
+
+    fn detail(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl fmt::Show for GlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GlError::Compile { ref stage, ref file, ref sources, ref diagnostics } =>
+                write!(f, "{} shader {} failed to compile:\n{}",
+                    stage, file, diagnostic::render(diagnostics.as_slice(), sources.as_slice())),
+            GlError::Preprocess { ref file, line, ref message } =>
+                write!(f, "{}:{}: {}", file, line, message),
+            GlError::Link { ref log } =>
+                write!(f, "program link failed:\n{}", log),
+            GlError::Call { code, call_site } =>
+                write!(f, "{} failed with {} (0x{:x})", call_site, GlError::code_name(code), code),
+            GlError::Creation { resource } =>
+                write!(f, "could not create {}", resource),
+            GlError::Uninitialized { object } =>
+                write!(f, "{} is used before init", object),
+            GlError::Source { ref file, ref error } =>
+                write!(f, "could not read {}: {}", file, error),
+            GlError::Uniform { ref name, reason } =>
+                write!(f, "uniform {}: {}", name, reason),
+            GlError::Framebuffer { status } =>
+                write!(f, "framebuffer is not complete: {} (0x{:x})", GlError::framebuffer_status_name(status), status),
+        }
+    }
+}
+
+/// A shader object that is deleted when dropped
+pub struct Shader {
+    id: GLuint,
+}
+
+impl Shader {
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+}
+
+impl Drop for Shader {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteShader(self.id); }
+    }
+}
+
+/// A program object that is deleted when dropped.
+/// The active uniforms are looked up once after linking and set by name.
+pub struct Program {
+    id: GLuint,
+    uniforms: HashMap<String, uniform::UniformInfo>,
+}
+
+impl Program {
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn uniform_info(&self, name: &str) -> Option<&uniform::UniformInfo> {
+        self.uniforms.get(name)
+    }
+
+    /// Set a uniform. The program doesn't need to be bound.
+    /// Fails when the program has no such active uniform, which catches misspelled names and
+    /// reloaded shaders that lost a uniform they must use
+    pub fn set_uniform<T: uniform::Uniform>(&self, name: &str, value: &T) -> Result<(), GlError> {
+        self.set_uniform_array(name, ::std::slice::ref_slice(value))
+    }
+
+    /// Set a uniform if the program uses it, for uniforms that the compiler may optimize out
+    /// depending on the shading, such as the material and shadow parameters
+    pub fn set_optional_uniform<T: uniform::Uniform>(&self, name: &str, value: &T) -> Result<(), GlError> {
+        if self.uniforms.contains_key(name) {
+            self.set_uniform(name, value)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Set the first elements of a uniform array if the program uses it
+    pub fn set_optional_uniform_array<T: uniform::Uniform>(&self, name: &str, values: &[T]) -> Result<(), GlError> {
+        if self.uniforms.contains_key(name) {
+            self.set_uniform_array(name, values)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Set the first values.len() elements of a uniform array
+    pub fn set_uniform_array<T: uniform::Uniform>(&self, name: &str, values: &[T]) -> Result<(), GlError> {
+        let info = match self.uniforms.get(name) {
+            Some(i) => i,
+            None => return Err(GlError::Uniform { name: name.to_string(), reason: "not an active uniform" }),
+        };
+        if !T::accepts(info.gl_type) {
+            return Err(GlError::Uniform { name: name.to_string(), reason: "type does not match" });
+        }
+        if values.len() > info.size as uint {
+            return Err(GlError::Uniform { name: name.to_string(), reason: "too many elements" });
+        }
+        unsafe {
+            T::upload(self.id, info.location, values.len() as GLsizei, values.as_ptr());
+        }
+        check_error("glProgramUniform")
+    }
+
+    pub fn bind(&self) -> Result<(), GlError> {
+        unsafe { gl::UseProgram(self.id); }
+        check_error("glUseProgram")
+    }
+
+    pub fn unbind(&self) {
+        unsafe { gl::UseProgram(0); }
+    }
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteProgram(self.id); }
+    }
+}
+
+/// A buffer object that is deleted when dropped
+pub struct Buffer {
+    id: GLuint,
+    target: GLenum,
+}
+
+impl Buffer {
+    /// Create a buffer that will be bound to target (ARRAY_BUFFER, ELEMENT_ARRAY_BUFFER, ...)
+    pub fn new(target: GLenum) -> Result<Buffer, GlError> {
+        let mut id = 0;
+        unsafe { gl::GenBuffers(1, &mut id); }
+        try!(check_error("glGenBuffers"));
+        if id == 0 {
+            return Err(GlError::Creation { resource: "buffer" });
+        }
+        Ok(Buffer {
+            id: id,
+            target: target,
+        })
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn bind(&self) -> Result<(), GlError> {
+        unsafe { gl::BindBuffer(self.target, self.id); }
+        check_error("glBindBuffer")
+    }
+
+    /// Bind the buffer and copy data to it
+    pub fn set_data<T>(&self, data: &[T], usage: GLenum) -> Result<(), GlError> {
+        self.set_raw_data((data.len() * mem::size_of::<T>()) as GLsizeiptr,
+            data.as_ptr() as *const libc::c_void, usage)
+    }
+
+    /// Bind the buffer and copy size bytes from data to it
+    pub fn set_raw_data(&self, size: GLsizeiptr, data: *const libc::c_void, usage: GLenum) -> Result<(), GlError> {
+        try!(self.bind());
+        unsafe { gl::BufferData(self.target, size, data, usage); }
+        check_error("glBufferData")
+    }
+
+    /// Bind the buffer and replace its contents starting at offset bytes with data.
+    /// The buffer must already be large enough
+    pub fn set_sub_data<T>(&self, offset: GLintptr, data: &[T]) -> Result<(), GlError> {
+        try!(self.bind());
+        unsafe {
+            gl::BufferSubData(self.target, offset, (data.len() * mem::size_of::<T>()) as GLsizeiptr,
+                data.as_ptr() as *const libc::c_void);
+        }
+        check_error("glBufferSubData")
+    }
+
+    /// Bind the buffer to an indexed binding point of an indexed target such as UNIFORM_BUFFER
+    pub fn bind_base(&self, index: GLuint) -> Result<(), GlError> {
+        unsafe { gl::BindBufferBase(self.target, index, self.id); }
+        check_error("glBindBufferBase")
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteBuffers(1, &self.id); }
+    }
+}
+
+/// A vertex array object that is deleted when dropped
+pub struct VertexArray {
+    id: GLuint,
+}
+
+impl VertexArray {
+    pub fn new() -> Result<VertexArray, GlError> {
+        let mut id = 0;
+        unsafe { gl::GenVertexArrays(1, &mut id); }
+        try!(check_error("glGenVertexArrays"));
+        if id == 0 {
+            return Err(GlError::Creation { resource: "vertex array" });
+        }
+        Ok(VertexArray { id: id })
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn bind(&self) -> Result<(), GlError> {
+        unsafe { gl::BindVertexArray(self.id); }
+        check_error("glBindVertexArray")
+    }
+
+    pub fn unbind(&self) {
+        unsafe { gl::BindVertexArray(0); }
+    }
+}
+
+impl Drop for VertexArray {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteVertexArrays(1, &self.id); }
+    }
+}
+
+/// Compile src as a shader of type ty. file names the source in the diagnostics
+pub fn compile_shader(file: &str, src: &str, ty: GLenum) -> Result<Shader, GlError> {
+    let shader;
+    unsafe {
+        let id = gl::CreateShader(ty);
+        if id == 0 {
+            return Err(GlError::Creation { resource: "shader" });
+        }
+        // wrap it right away so the shader is deleted when compiling fails
+        shader = Shader { id: id };
+        // Attempt to compile the shader
+        src.with_c_str(|ptr| gl::ShaderSource(id, 1, &ptr, ptr::null()));
+        gl::CompileShader(id);
+        // Get the compile status
+        let mut status = gl::FALSE as GLint;
+        gl::GetShaderiv(id, gl::COMPILE_STATUS, &mut status);
+        // Fail on error
+        if status != (gl::TRUE as GLint) {
+            let mut len = 0;
+            gl::GetShaderiv(id, gl::INFO_LOG_LENGTH, &mut len);
+            let mut buf = Vec::from_elem(len as uint - 1, 0u8); // subtract 1 to skip the trailing null character
+            gl::GetShaderInfoLog(id, len, ptr::null_mut(), buf.as_mut_ptr() as *mut GLchar);
+            // the log is only informational so keep whatever is readable
+            let log = String::from_utf8_lossy(buf.as_slice()).into_owned();
+            return Err(GlError::Compile {
+                stage: ShaderStage::from_gl(ty),
+                file: file.to_string(),
+                sources: vec![(file.to_string(), src.to_string())],
+                diagnostics: diagnostic::parse_log(log.as_slice(), file),
+            });
+        }
+    }
+    Ok(shader)
+}
+
+/// Compile a preprocessed shader, reporting errors against the original files
+pub fn compile_preprocessed(p: &preprocess::Preprocessed, ty: GLenum) -> Result<Shader, GlError> {
+    match compile_shader(p.file.as_slice(), p.source.as_slice(), ty) {
+        Err(GlError::Compile { stage, file, diagnostics, .. }) => Err(GlError::Compile {
+            stage: stage,
+            file: file,
+            sources: p.files.clone(),
+            diagnostics: p.map_diagnostics(diagnostics),
+        }),
+        r => r,
+    }
+}
+
+/// Link the shaders into a program.
+/// The shaders are detached after linking so they can be deleted when they are dropped.
+pub fn link_program(vs: &Shader, fs: &Shader) -> Result<Program, GlError> {
+    let mut program;
+    unsafe {
+        let id = gl::CreateProgram();
+        if id == 0 {
+            return Err(GlError::Creation { resource: "program" });
+        }
+        program = Program { id: id, uniforms: HashMap::new() };
+        gl::AttachShader(id, vs.id);
+        gl::AttachShader(id, fs.id);
+        gl::LinkProgram(id);
+        gl::DetachShader(id, vs.id);
+        gl::DetachShader(id, fs.id);
+        // Get the link status
+        let mut status = gl::FALSE as GLint;
+        gl::GetProgramiv(id, gl::LINK_STATUS, &mut status);
+        // Fail on error
+        if status != (gl::TRUE as GLint) {
+            let mut len: GLint = 0;
+            gl::GetProgramiv(id, gl::INFO_LOG_LENGTH, &mut len);
+            let mut buf = Vec::from_elem(len as uint - 1, 0u8); // subtract 1 to skip the trailing null character
+            gl::GetProgramInfoLog(id, len, ptr::null_mut(), buf.as_mut_ptr() as *mut GLchar);
+            let log = String::from_utf8_lossy(buf.as_slice()).into_owned();
+            return Err(GlError::Link { log: log });
+        }
+    }
+    program.uniforms = uniform::introspect(program.id);
+    Ok(program)
+}
+
+pub fn read_shader(path: &Path) -> IoResult<String> {
+    let mut sf = try!(File::open(path));
+    let ss = try!(sf.read_to_string());
+    Ok(ss)
+}
+
+/// Check the GL error flag. call_site names the GL call made right before
+pub fn check_error(call_site: &'static str) -> Result<(), GlError> {
+    let err;
+    unsafe {
+        err = gl::GetError();
+    }
+    match err {
+        gl::NO_ERROR => Ok(()),
+        code => Err(GlError::Call { code: code, call_site: call_site }),
+    }
+}
@@ -0,0 +1,208 @@
+use std::io::fs;
+
+use glutil;
+use glutil::GlError;
+use glutil::diagnostic::Diagnostic;
+use glutil::reload::SourceMode;
+
+/// Shared shader code that can be included from any shader, by the name relative to src/
+static EMBEDDED: &'static [(&'static str, &'static str)] = &[
+    ("common/header.glsl", include_str!("../common/header.glsl")),
+    ("common/lighting.glsl", include_str!("../common/lighting.glsl")),
+    ("common/material.glsl", include_str!("../common/material.glsl")),
+    ("common/color.glsl", include_str!("../common/color.glsl")),
+    ("common/pbr.glsl", include_str!("../common/pbr.glsl")),
+    ("common/light.glsl", include_str!("../common/light.glsl")),
+    ("common/shadow.glsl", include_str!("../common/shadow.glsl")),
+    ("common/shading.glsl", include_str!("../common/shading.glsl")),
+];
+
+/// Resolves the names used in #include, either from the embedded library or from disk
+pub struct Library {
+    mode: SourceMode,
+}
+
+impl Library {
+    pub fn new(mode: &SourceMode) -> Library {
+        Library { mode: mode.clone() }
+    }
+
+    pub fn on_disk(&self) -> bool {
+        match self.mode {
+            SourceMode::Embedded => false,
+            SourceMode::Disk(_) => true,
+        }
+    }
+
+    pub fn load(&self, name: &str) -> Result<String, GlError> {
+        match self.mode {
+            SourceMode::Embedded => match EMBEDDED.iter().find(|&&(n, _)| n == name) {
+                Some(&(_, src)) => Ok(src.to_string()),
+                None => Err(GlError::Source {
+                    file: name.to_string(),
+                    error: ::std::io::standard_error(::std::io::FileNotFound),
+                }),
+            },
+            SourceMode::Disk(ref dir) => {
+                let p = dir.join(name);
+                glutil::read_shader(&p).map_err(|e| GlError::Source {
+                    file: p.display().to_string(),
+                    error: e,
+                })
+            },
+        }
+    }
+
+    /// Modification time of a file on disk. Always None for embedded sources
+    pub fn modified(&self, name: &str) -> Option<u64> {
+        match self.mode {
+            SourceMode::Embedded => None,
+            SourceMode::Disk(ref dir) => fs::stat(&dir.join(name)).ok().map(|s| s.modified),
+        }
+    }
+}
+
+/// A shader source with every #include expanded and the defines injected
+pub struct Preprocessed {
+    pub file: String,
+    pub source: String,
+    /// file name and 1 based line in that file for each line of source
+    pub line_map: Vec<(String, uint)>,
+    /// name and text of the root file and every included file
+    pub files: Vec<(String, String)>,
+}
+
+impl Preprocessed {
+    /// Point the diagnostics reported for source back to the original files and lines
+    pub fn map_diagnostics(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        diagnostics.into_iter().map(|mut d| {
+            match d.line {
+                Some(l) if l >= 1 && l <= self.line_map.len() => {
+                    let (ref file, line) = self.line_map[l - 1];
+                    d.file = file.clone();
+                    d.line = Some(line);
+                },
+                _ => (),
+            }
+            d
+        }).collect()
+    }
+}
+
+/// Expand `#include "name"` lines of src with the files in library and add `#define name value`
+/// for each define right after the #version line.
+/// A file is only expanded at its first #include, later ones are dropped.
+pub fn preprocess(file: &str, src: &str, defines: &[(String, String)], library: &Library)
+    -> Result<Preprocessed, GlError> {
+    let mut out = Preprocessed {
+        file: file.to_string(),
+        source: String::new(),
+        line_map: Vec::new(),
+        files: vec![(file.to_string(), src.to_string())],
+    };
+    let mut stack = vec![file.to_string()];
+    let mut defined = false;
+    try!(expand(file, src, defines, library, &mut stack, &mut defined, &mut out));
+    if !defined {
+        // there was no #version so the defines go in front of everything
+        let (mut source, mut line_map) = define_lines(defines);
+        source.push_str(out.source.as_slice());
+        line_map.extend(out.line_map.into_iter());
+        out.source = source;
+        out.line_map = line_map;
+    }
+    Ok(out)
+}
+
+fn expand(file: &str, src: &str, defines: &[(String, String)], library: &Library,
+    stack: &mut Vec<String>, defined: &mut bool, out: &mut Preprocessed) -> Result<(), GlError> {
+    for (i, line) in src.lines().enumerate() {
+        let trimmed = line.trim_left();
+        if trimmed.starts_with("#include") {
+            let name = match include_name(trimmed) {
+                Some(n) => n,
+                None => return Err(GlError::Preprocess {
+                    file: file.to_string(),
+                    line: i + 1,
+                    message: "expected #include \"file\"".to_string(),
+                }),
+            };
+            if stack.iter().any(|s| s.as_slice() == name) {
+                return Err(GlError::Preprocess {
+                    file: file.to_string(),
+                    line: i + 1,
+                    message: format!("{} includes itself", name),
+                });
+            }
+            if out.files.iter().any(|&(ref n, _)| n.as_slice() == name) {
+                // every file is pasted once, so headers shared by several includes don't clash
+                continue;
+            }
+            let text = try!(library.load(name));
+            out.files.push((name.to_string(), text.clone()));
+            stack.push(name.to_string());
+            try!(expand(name, text.as_slice(), defines, library, stack, defined, out));
+            stack.pop();
+            continue;
+        }
+        out.source.push_str(line);
+        out.source.push('\n');
+        out.line_map.push((file.to_string(), i + 1));
+        if !*defined && trimmed.starts_with("#version") {
+            let (source, line_map) = define_lines(defines);
+            out.source.push_str(source.as_slice());
+            out.line_map.extend(line_map.into_iter());
+            *defined = true;
+        }
+    }
+    Ok(())
+}
+
+/// #define lines and their line map, where the line is the index of the define
+fn define_lines(defines: &[(String, String)]) -> (String, Vec<(String, uint)>) {
+    let mut source = String::new();
+    let mut line_map = Vec::new();
+    for (i, &(ref name, ref value)) in defines.iter().enumerate() {
+        source.push_str(format!("#define {} {}\n", name, value).as_slice());
+        line_map.push(("<defines>".to_string(), i + 1));
+    }
+    (source, line_map)
+}
+
+fn include_name(line: &str) -> Option<&str> {
+    let rest = line.slice_from("#include".len()).trim();
+    if rest.len() >= 2 && rest.starts_with("\"") && rest.ends_with("\"") {
+        Some(rest.slice(1, rest.len() - 1))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use glutil::reload::SourceMode;
+    use super::{Library,preprocess};
+
+    #[test]
+    fn shared_include_is_expanded_once() {
+        let library = Library::new(&SourceMode::Embedded);
+        let src = "#version 430\n#include \"common/light.glsl\"\n#include \"common/shading.glsl\"\n";
+        let p = preprocess("test.glsl", src, &[], &library).unwrap();
+        assert_eq!(p.source.as_slice().matches_str("struct Light {").count(), 1);
+        assert_eq!(p.source.as_slice().matches_str("uniform Lights").count(), 1);
+    }
+
+    #[test]
+    fn line_map_points_into_included_files() {
+        let library = Library::new(&SourceMode::Embedded);
+        let src = "#version 430\n#include \"common/lighting.glsl\"\nvoid main() {}\n";
+        let defines = vec![("N".to_string(), "1".to_string())];
+        let p = preprocess("test.glsl", src, defines.as_slice(), &library).unwrap();
+        let included = include_str!("../common/lighting.glsl").lines().count();
+        assert_eq!(p.line_map.len(), 1 + 1 + included + 1);
+        assert_eq!(p.line_map[0], ("test.glsl".to_string(), 1));
+        assert_eq!(p.line_map[1], ("<defines>".to_string(), 1));
+        assert_eq!(p.line_map[2], ("common/lighting.glsl".to_string(), 1));
+        assert_eq!(p.line_map[p.line_map.len() - 1], ("test.glsl".to_string(), 3));
+    }
+}
@@ -0,0 +1,147 @@
+extern crate gl;
+
+use std::io;
+use std::ops::Deref;
+
+use glutil;
+use glutil::{GlError,Program};
+use glutil::preprocess;
+
+/// Where shader sources are loaded from
+#[deriving(Clone)]
+pub enum SourceMode {
+    /// Use the sources baked into the binary with include_str!
+    Embedded,
+    /// Load the sources from files under the given directory and reload them when they change
+    Disk(Path),
+}
+
+/// A shader source file.
+/// name is the path relative to the source directory, which is also used in diagnostics
+pub struct ShaderFile {
+    pub name: &'static str,
+    pub embedded: &'static str,
+}
+
+impl ShaderFile {
+    pub fn new(name: &'static str, embedded: &'static str) -> ShaderFile {
+        ShaderFile {
+            name: name,
+            embedded: embedded,
+        }
+    }
+
+    fn load(&self, library: &preprocess::Library) -> Result<String, GlError> {
+        if library.on_disk() {
+            library.load(self.name)
+        } else {
+            Ok(self.embedded.to_string())
+        }
+    }
+}
+
+/// A program built from a vertex and fragment shader file.
+/// In SourceMode::Disk the files and everything they include are watched,
+/// and the program is rebuilt when one of them changes.
+pub struct ReloadableProgram {
+    library: preprocess::Library,
+    vertex: ShaderFile,
+    fragment: ShaderFile,
+    defines: Vec<(String, String)>,
+    /// name and modification time of every file used by the program when it was last built
+    watched: Vec<(String, Option<u64>)>,
+    program: Program,
+    /// why the last reload failed, until one succeeds
+    last_error: Option<GlError>,
+}
+
+impl ReloadableProgram {
+    pub fn new(mode: &SourceMode, vertex: ShaderFile, fragment: ShaderFile) -> Result<ReloadableProgram, GlError> {
+        ReloadableProgram::with_defines(mode, vertex, fragment, Vec::new())
+    }
+
+    /// Build the program with a `#define name value` added to both shaders for each define
+    pub fn with_defines(mode: &SourceMode, vertex: ShaderFile, fragment: ShaderFile,
+        defines: Vec<(String, String)>) -> Result<ReloadableProgram, GlError> {
+        let library = preprocess::Library::new(mode);
+        let (program, files) = try!(build(&library, &vertex, &fragment, defines.as_slice()));
+        let watched = watch(&library, files);
+        Ok(ReloadableProgram {
+            library: library,
+            vertex: vertex,
+            fragment: fragment,
+            defines: defines,
+            watched: watched,
+            program: program,
+            last_error: None,
+        })
+    }
+
+    /// Rebuild the program when a source file was modified since it was last loaded.
+    /// When the new sources fail to build, the error and its diagnostics are written to stderr
+    /// and kept in last_error, and the current program stays in use.
+    /// Returns true when the program was replaced.
+    pub fn reload_if_changed(&mut self) -> bool {
+        let changed = self.watched.iter().any(|&(ref name, modified)| {
+            self.library.modified(name.as_slice()) != modified
+        });
+        if !changed {
+            return false;
+        }
+        // remember the times even on failure so a broken file is not rebuilt every frame
+        for &(ref name, ref mut modified) in self.watched.iter_mut() {
+            *modified = self.library.modified(name.as_slice());
+        }
+        match build(&self.library, &self.vertex, &self.fragment, self.defines.as_slice()) {
+            Ok((p, files)) => {
+                let _ = writeln!(&mut io::stderr(), "Reloaded {} and {}", self.vertex.name, self.fragment.name);
+                self.program = p;
+                self.last_error = None;
+                // includes may have been added or removed
+                self.watched = watch(&self.library, files);
+                true
+            },
+            Err(e) => {
+                let _ = writeln!(&mut io::stderr(), "Reload failed, keeping the previous program: {}", e);
+                self.last_error = Some(e);
+                false
+            },
+        }
+    }
+
+    /// The error of the last reload when it failed, with the diagnostics of the compile log
+    pub fn last_error(&self) -> Option<&GlError> {
+        self.last_error.as_ref()
+    }
+}
+
+impl Deref<Program> for ReloadableProgram {
+    fn deref<'a>(&'a self) -> &'a Program {
+        &self.program
+    }
+}
+
+/// Build the program and return it with the names of every file it was built from
+fn build(library: &preprocess::Library, vertex: &ShaderFile, fragment: &ShaderFile,
+    defines: &[(String, String)]) -> Result<(Program, Vec<String>), GlError> {
+    let vp = try!(preprocess::preprocess(vertex.name, try!(vertex.load(library)).as_slice(), defines, library));
+    let fp = try!(preprocess::preprocess(fragment.name, try!(fragment.load(library)).as_slice(), defines, library));
+    let vs = try!(glutil::compile_preprocessed(&vp, gl::VERTEX_SHADER));
+    let fs = try!(glutil::compile_preprocessed(&fp, gl::FRAGMENT_SHADER));
+    // shaders are deleted at the end of this scope since we've finished linking it
+    let program = try!(glutil::link_program(&vs, &fs));
+    let mut files : Vec<String> = Vec::new();
+    for &(ref name, _) in vp.files.iter().chain(fp.files.iter()) {
+        if !files.contains(name) {
+            files.push(name.clone());
+        }
+    }
+    Ok((program, files))
+}
+
+fn watch(library: &preprocess::Library, files: Vec<String>) -> Vec<(String, Option<u64>)> {
+    files.into_iter().map(|name| {
+        let modified = library.modified(name.as_slice());
+        (name, modified)
+    }).collect()
+}
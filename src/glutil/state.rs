@@ -0,0 +1,130 @@
+extern crate gl;
+
+use gl::types::GLenum;
+
+use glutil::{GlError,check_error};
+
+#[deriving(Clone, PartialEq, Eq)]
+pub enum Cull {
+    Back,
+    Front,
+}
+
+#[deriving(Clone, PartialEq, Eq)]
+pub enum Blend {
+    /// source alpha over the destination
+    Alpha,
+    Additive,
+}
+
+/// Fixed function state an object needs while it is drawn
+#[deriving(Clone, PartialEq, Eq)]
+pub struct RenderState {
+    pub depth_test: bool,
+    pub depth_write: bool,
+    /// faces that are discarded. Front faces are counter-clockwise
+    pub cull: Option<Cull>,
+    pub blend: Option<Blend>,
+}
+
+impl RenderState {
+    /// Closed solid geometry
+    pub fn opaque() -> RenderState {
+        RenderState {
+            depth_test: true,
+            depth_write: true,
+            cull: Some(Cull::Back),
+            blend: None,
+        }
+    }
+
+    /// Lines and open geometry that should be seen from both sides
+    pub fn double_sided() -> RenderState {
+        RenderState {
+            cull: None,
+            ..RenderState::opaque()
+        }
+    }
+}
+
+/// Remembers the GL state so only what differs from the last applied RenderState is changed
+pub struct StateTracker {
+    /// None until the first apply, since the state left by the driver is unknown
+    current: Option<RenderState>,
+}
+
+impl StateTracker {
+    pub fn new() -> StateTracker {
+        StateTracker { current: None }
+    }
+
+    /// Forget the current state, for when something else changed GL state directly
+    pub fn invalidate(&mut self) {
+        self.current = None;
+    }
+
+    pub fn apply(&mut self, state: &RenderState) -> Result<(), GlError> {
+        let (depth_test, depth_write, cull, blend) = match self.current {
+            Some(ref c) => (c.depth_test != state.depth_test,
+                c.depth_write != state.depth_write,
+                c.cull != state.cull,
+                c.blend != state.blend),
+            None => (true, true, true, true),
+        };
+        unsafe {
+            if depth_test {
+                set_capability(gl::DEPTH_TEST, state.depth_test);
+            }
+            if depth_write {
+                gl::DepthMask(if state.depth_write { gl::TRUE } else { gl::FALSE });
+            }
+            if cull {
+                match state.cull {
+                    Some(ref c) => {
+                        gl::Enable(gl::CULL_FACE);
+                        gl::CullFace(match *c { Cull::Back => gl::BACK, Cull::Front => gl::FRONT });
+                    },
+                    None => gl::Disable(gl::CULL_FACE),
+                }
+            }
+            if blend {
+                match state.blend {
+                    Some(ref b) => {
+                        gl::Enable(gl::BLEND);
+                        match *b {
+                            Blend::Alpha => gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA),
+                            Blend::Additive => gl::BlendFunc(gl::ONE, gl::ONE),
+                        }
+                    },
+                    None => gl::Disable(gl::BLEND),
+                }
+            }
+        }
+        try!(check_error("applying render state"));
+        self.current = Some(state.clone());
+        Ok(())
+    }
+
+    /// Clear the color and depth buffer.
+    /// Depth writes are turned on first since glClear respects the depth mask
+    pub fn clear(&mut self, r: f32, g: f32, b: f32, a: f32) -> Result<(), GlError> {
+        let state = match self.current {
+            Some(ref c) => RenderState { depth_write: true, ..c.clone() },
+            None => RenderState::opaque(),
+        };
+        try!(self.apply(&state));
+        unsafe {
+            gl::ClearColor(r, g, b, a);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+        check_error("glClear")
+    }
+}
+
+unsafe fn set_capability(cap: GLenum, enabled: bool) {
+    if enabled {
+        gl::Enable(cap);
+    } else {
+        gl::Disable(cap);
+    }
+}
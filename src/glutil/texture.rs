@@ -0,0 +1,76 @@
+extern crate gl;
+
+use gl::types::{GLenum,GLuint,GLint,GLsizei};
+use std::ptr;
+
+use glutil::{GlError,check_error};
+
+/// A texture object that is deleted when dropped
+pub struct Texture {
+    id: GLuint,
+    target: GLenum,
+}
+
+impl Texture {
+    /// Create a texture that will be bound to target (TEXTURE_2D, TEXTURE_2D_ARRAY, ...)
+    pub fn new(target: GLenum) -> Result<Texture, GlError> {
+        let mut id = 0;
+        unsafe { gl::GenTextures(1, &mut id); }
+        try!(check_error("glGenTextures"));
+        if id == 0 {
+            return Err(GlError::Creation { resource: "texture" });
+        }
+        Ok(Texture {
+            id: id,
+            target: target,
+        })
+    }
+
+    /// An array of depth textures that are compared against a reference value when sampled,
+    /// for use with sampler2DArrayShadow.
+    /// Linear filtering makes the hardware blend four comparisons, and lookups outside
+    /// of the texture are never in shadow
+    pub fn depth_array(width: GLsizei, height: GLsizei, layers: GLsizei) -> Result<Texture, GlError> {
+        let texture = try!(Texture::new(gl::TEXTURE_2D_ARRAY));
+        try!(texture.bind(0));
+        let border = [1.0f32, 1.0, 1.0, 1.0];
+        unsafe {
+            gl::TexImage3D(gl::TEXTURE_2D_ARRAY, 0, gl::DEPTH_COMPONENT24 as GLint, width, height, layers, 0,
+                gl::DEPTH_COMPONENT, gl::FLOAT, ptr::null());
+            try!(check_error("glTexImage3D"));
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_BORDER as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_BORDER as GLint);
+            gl::TexParameterfv(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_BORDER_COLOR, border.as_ptr());
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_COMPARE_MODE,
+                gl::COMPARE_REF_TO_TEXTURE as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_COMPARE_FUNC, gl::LEQUAL as GLint);
+        }
+        try!(check_error("glTexParameter"));
+        Ok(texture)
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn target(&self) -> GLenum {
+        self.target
+    }
+
+    /// Bind the texture to a texture unit, counting from 0
+    pub fn bind(&self, unit: GLuint) -> Result<(), GlError> {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(self.target, self.id);
+        }
+        check_error("glBindTexture")
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteTextures(1, &self.id); }
+    }
+}
@@ -0,0 +1,110 @@
+extern crate gl;
+extern crate cgmath;
+
+use gl::types::{GLenum,GLuint,GLint,GLsizei,GLchar};
+use std::collections::HashMap;
+use cgmath::{Vector3,Vector4,Matrix3,Matrix4};
+
+/// An active uniform of a linked program
+pub struct UniformInfo {
+    pub location: GLint,
+    /// type reported by glGetActiveUniform, such as FLOAT_VEC3
+    pub gl_type: GLenum,
+    /// number of elements. 1 unless the uniform is an array
+    pub size: GLint,
+}
+
+/// A value that can be uploaded to a uniform
+pub trait Uniform {
+    /// Whether a uniform of the given GL type can hold this value
+    fn accepts(gl_type: GLenum) -> bool;
+    /// Upload count values from values to location of program
+    unsafe fn upload(program: GLuint, location: GLint, count: GLsizei, values: *const Self);
+}
+
+impl Uniform for f32 {
+    fn accepts(gl_type: GLenum) -> bool { gl_type == gl::FLOAT }
+    unsafe fn upload(program: GLuint, location: GLint, count: GLsizei, values: *const f32) {
+        gl::ProgramUniform1fv(program, location, count, values);
+    }
+}
+
+impl Uniform for i32 {
+    fn accepts(gl_type: GLenum) -> bool {
+        match gl_type {
+            // samplers are set with the texture unit
+            gl::INT | gl::BOOL | gl::SAMPLER_2D | gl::SAMPLER_2D_SHADOW | gl::SAMPLER_CUBE
+                | gl::SAMPLER_2D_ARRAY_SHADOW => true,
+            _ => false,
+        }
+    }
+    unsafe fn upload(program: GLuint, location: GLint, count: GLsizei, values: *const i32) {
+        gl::ProgramUniform1iv(program, location, count, values);
+    }
+}
+
+impl Uniform for Vector3<f32> {
+    fn accepts(gl_type: GLenum) -> bool { gl_type == gl::FLOAT_VEC3 }
+    unsafe fn upload(program: GLuint, location: GLint, count: GLsizei, values: *const Vector3<f32>) {
+        gl::ProgramUniform3fv(program, location, count, values as *const f32);
+    }
+}
+
+impl Uniform for Vector4<f32> {
+    fn accepts(gl_type: GLenum) -> bool { gl_type == gl::FLOAT_VEC4 }
+    unsafe fn upload(program: GLuint, location: GLint, count: GLsizei, values: *const Vector4<f32>) {
+        gl::ProgramUniform4fv(program, location, count, values as *const f32);
+    }
+}
+
+impl Uniform for Matrix3<f32> {
+    fn accepts(gl_type: GLenum) -> bool { gl_type == gl::FLOAT_MAT3 }
+    unsafe fn upload(program: GLuint, location: GLint, count: GLsizei, values: *const Matrix3<f32>) {
+        // cgmath matrices are column major like GLSL
+        gl::ProgramUniformMatrix3fv(program, location, count, gl::FALSE, values as *const f32);
+    }
+}
+
+impl Uniform for Matrix4<f32> {
+    fn accepts(gl_type: GLenum) -> bool { gl_type == gl::FLOAT_MAT4 }
+    unsafe fn upload(program: GLuint, location: GLint, count: GLsizei, values: *const Matrix4<f32>) {
+        gl::ProgramUniformMatrix4fv(program, location, count, gl::FALSE, values as *const f32);
+    }
+}
+
+/// Query the active uniforms of a linked program, keyed by name.
+/// Arrays are registered without the trailing "[0]", and uniforms in blocks are skipped
+/// since they don't have a location.
+pub fn introspect(program: GLuint) -> HashMap<String, UniformInfo> {
+    let mut uniforms = HashMap::new();
+    unsafe {
+        let mut count = 0;
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+        let mut max_len = 0;
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_len);
+        for i in range(0, count as GLuint) {
+            let mut buf = Vec::from_elem(max_len as uint, 0u8);
+            let mut len = 0;
+            let mut size = 0;
+            let mut ty = 0;
+            gl::GetActiveUniform(program, i, max_len, &mut len, &mut size, &mut ty,
+                buf.as_mut_ptr() as *mut GLchar);
+            buf.truncate(len as uint);
+            let mut name = String::from_utf8_lossy(buf.as_slice()).into_owned();
+            let location = name.with_c_str(|n| gl::GetUniformLocation(program, n));
+            if location < 0 {
+                continue;
+            }
+            if name.as_slice().ends_with("[0]") {
+                let l = name.len() - 3;
+                name.truncate(l);
+            }
+            uniforms.insert(name, UniformInfo {
+                location: location,
+                gl_type: ty,
+                size: size,
+            });
+        }
+    }
+    uniforms
+}
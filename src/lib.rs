@@ -0,0 +1,21 @@
+extern crate glutin;
+extern crate libc;
+extern crate gl;
+extern crate cgmath;
+
+pub mod game;
+pub mod glutil;
+pub mod mesh;
+pub mod christmas_star;
+pub mod light;
+pub mod control;
+pub mod camera;
+pub mod transform;
+pub mod material;
+pub mod shadow;
+pub mod backdrop;
+pub mod scene;
+pub mod offscreen;
+pub mod png;
+pub mod compare;
+pub mod software;
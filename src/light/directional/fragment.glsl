@@ -1,7 +0,0 @@
-#version 430
-
-in vec4 color;
-out vec4 pix_color;
-void main() {
-    pix_color = color;
-}
\ No newline at end of file
@@ -1,203 +1,142 @@
 extern crate gl;
 extern crate cgmath;
 
-use gl::types::{GLuint,GLfloat,GLsizeiptr,GLboolean};
-use std::ptr;
-use std::mem;
 use std::f32::consts;
 use std::num::FloatMath;
-use cgmath::{Vector3,Vector4};
+use cgmath::{Matrix,Matrix4,Point,Point3,Vector,Vector3,Vector4,EuclideanVector};
 use glutil;
+use glutil::reload;
+use light;
+use light::gizmo;
+use mesh;
 use game;
 use control;
 
+/// Light from infinitely far away, like the sun. It reaches everything from the same direction
 pub struct Light {
-    position: cgmath::Vector3<f32>,
-    resource : GlResource,
-}
-
-struct Vertex {
-    position: cgmath::Vector3<f32>,
-    diffuse_color: cgmath::Vector4<f32>,
-}
-
-impl Vertex {
-    fn new(pos: cgmath::Vector3<f32>, diffuse: cgmath::Vector4<f32>) -> Vertex {
-        Vertex {
-            position : pos,
-            diffuse_color : diffuse,
-        }
-    }
-}
-struct GlResource {
-    shader_program: GLuint,
-    vao: GLuint,
-    vbo: GLuint,
-    indice_num : i32,
-    mvp_name : String,
+    /// points from the lit surfaces towards the light
+    direction: Vector3<f32>,
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+    /// light that reaches every surface regardless of its direction
+    pub ambient: Vector3<f32>,
+    lines: gizmo::Lines,
+    gizmo: Option<gizmo::Gizmo>,
 }
 
 impl Light {
-    pub fn new(position: cgmath::Vector3<f32>) -> Light {
+    pub fn new(direction: Vector3<f32>) -> Light {
         Light {
-            position: position,
-            resource : GlResource {
-                shader_program : 0,
-                vao: 0,
-                vbo: 0,
-                indice_num: 0,
-                mvp_name : "mvp".to_string(),
-            },
+            direction: direction.normalize(),
+            color: Vector3::new(1.0, 1.0, 1.0),
+            intensity: 1.0,
+            ambient: Vector3::new(0.15, 0.15, 0.15),
+            lines: lines(),
+            gizmo: None,
         }
     }
 
+    pub fn direction(&self) -> Vector3<f32> {
+        self.direction
+    }
 
-    pub fn init(&mut self) -> Result<(), String> {
-        let vss = include_str!("vertex.glsl");
-        let fss = include_str!("fragment.glsl");
-        let vs = try!(glutil::compile_shader(vss.as_slice(), gl::VERTEX_SHADER));
-        let fs = try!(glutil::compile_shader(fss.as_slice(), gl::FRAGMENT_SHADER));
-        let prog = try!(glutil::link_program(vs, fs));
+    pub fn set_direction(&mut self, direction: Vector3<f32>) {
+        self.direction = direction.normalize();
+    }
+}
 
-        // remove shaders since we've finished linking it
-        glutil::remove_shader(prog, vs);
-        glutil::remove_shader(prog, fs);
- 
-        let (vao, vbo, ind_num) = try!(init_buffers());
+impl light::Light for Light {
+    fn init(&mut self, mode: &reload::SourceMode) -> Result<(), glutil::GlError> {
+        self.gizmo = Some(try!(gizmo::Gizmo::new(mode, &self.lines)));
+        Ok(())
+    }
 
-        let r = &mut self.resource;
-        r.shader_program = prog;
-        r.vao = vao;
-        r.vbo = vbo;
-        r.indice_num = ind_num;
+    fn params(&self) -> light::Params {
+        let d = self.direction;
+        light::Params {
+            position: Vector4::new(d.x, d.y, d.z, 0.0),
+            color: self.color,
+            intensity: self.intensity,
+            attenuation: Vector3::new(1.0, 0.0, 0.0),
+            ambient: self.ambient,
+            spot_direction: Vector3::new(0.0, 0.0, 0.0),
+            spot_cos: (-1.0, -1.0),
+        }
+    }
 
-        Ok(())
+    fn gizmo_lines(&self) -> &gizmo::Lines {
+        &self.lines
     }
 
-    pub fn vector_from(&self, target : &cgmath::Vector3<f32>) -> cgmath::Vector3<f32> {
-        self.position.sub(target)
+    fn gizmo_model(&self) -> Matrix4<f32> {
+        gizmo_model(&self.direction)
     }
 
-    pub fn close(&mut self) {
-        let r = &mut self.resource;
-        unsafe {
-            gl::DeleteBuffers(1, &r.vbo);
-            gl::DeleteVertexArrays(1, &r.vao);
-        }
-        glutil::remove_program(r.shader_program);
-        r.shader_program = 0;
-        r.vbo = 0;
-        r.vao = 0;
+    fn shadow_view_projection(&self, center: &Vector3<f32>, radius: f32) -> Option<Matrix4<f32>> {
+        // parallel rays, so an orthographic box around the scene seen from the light
+        let eye = Point3::from_vec(&center.add_v(&self.direction.mul_s(radius * 2.0)));
+        let view = Matrix4::look_at(&eye, &Point3::from_vec(center), &light::up_vector(&self.direction));
+        let projection = cgmath::ortho(-radius, radius, -radius, radius, radius, radius * 3.0);
+        Some(projection.mul_m(&view))
     }
 }
 
 impl game::Object for Light {
-    fn update(&mut self, cs: &control::State) -> Result<(),String> {
+    fn update(&mut self, cs: &control::State, _dt: f32) -> Result<(),glutil::GlError> {
+        match self.gizmo {
+            Some(ref mut g) => g.reload_if_changed(),
+            None => (),
+        }
+        // the arrow keys tilt the direction up and down, and turn it around the y axis
         let delta = 0.01;
+        let (mut yaw, mut pitch) = (self.direction.x.atan2(self.direction.z), self.direction.y.asin());
         if cs.move_up {
-            self.position.y += delta;
+            pitch += delta;
         }
         if cs.move_down {
-            self.position.y += -delta;
+            pitch -= delta;
         }
         if cs.move_left {
-            self.position.x += -delta;
+            yaw -= delta;
         }
         if cs.move_right {
-            self.position.x += delta;
+            yaw += delta;
         }
+        let max_pitch = consts::FRAC_PI_2 - 0.01;
+        let pitch = pitch.max(-max_pitch).min(max_pitch);
+        self.direction = Vector3::new(pitch.cos() * yaw.sin(), pitch.sin(), pitch.cos() * yaw.cos());
         Ok(())
     }
 
-    fn draw(&self) -> Result<(),String> {
-        let r = &self.resource;
-        unsafe {
-            gl::UseProgram(r.shader_program);
-            try!(glutil::check_error());
-
-            let cstr = r.mvp_name.to_c_str();
-            let mvp = gl::GetUniformLocation(r.shader_program, cstr.as_ptr());
-            try!(glutil::check_error());
-            let p = &self.position;
-            let mvp_mat : Vec<f32> = vec![
-                1.0, 0.0, 0.0, 0.0,
-                0.0, 1.0, 0.0, 0.0,
-                0.0, 0.0, 1.0, 0.0,
-                p.x, p.y, p.z, 1.0, // apply translation
-            ];
-            gl::UniformMatrix4fv(mvp, 1, gl::FALSE, mem::transmute(&mvp_mat[0]));
-            try!(glutil::check_error());
-
-            gl::BindVertexArray(r.vao);
-            try!(glutil::check_error());
-            gl::DrawArrays(gl::LINE_LOOP, 0, r.indice_num);
-            try!(glutil::check_error());
-            gl::BindVertexArray(0);
-            gl::UseProgram(0);
-        }
-        Ok(())
+    fn draw(&self, ctx: &game::DrawContext) -> Result<(),glutil::GlError> {
+        let g = match self.gizmo {
+            Some(ref g) => g,
+            None => return Err(glutil::GlError::Uninitialized { object: "directional::Light" }),
+        };
+        g.draw(ctx, &gizmo_model(&self.direction))
     }
 }
 
-fn calculate_vertices(vertices: &mut Vec<Vertex>) {
-    let diffuse = cgmath::Vector4::new(1.0,0.0,0.0,1.0);
-    // calculate circle coordinates
-    let div = 8i;
-    let radius = 0.03;
-    let rad_per_div = consts::PI_2 / div.to_f32().unwrap();
-    for i in range(0,div) {
-        let cur_rad = rad_per_div * i.to_f32().unwrap();
-        let x = cur_rad.cos() * radius;
-        let y = cur_rad.sin() * radius;
-        let v = Vertex::new(cgmath::Vector3::new(x,y,0.0), diffuse);
-        vertices.push(v);
-    }
+/// There is no position, so the direction is shown as a ray coming in towards the origin.
+/// The mesh points along -z, which is turned to face away from the light
+fn gizmo_model(direction: &Vector3<f32>) -> Matrix4<f32> {
+    gizmo::facing(&direction.mul_s(1.2), direction)
 }
 
-fn init_buffers() -> Result<(GLuint, GLuint, i32), String> {
-    let mut vertices : Vec<Vertex> = Vec::new();
-    calculate_vertices(&mut vertices);
-    let mut vao = 0;
-    let mut vbo = 0;
-    let mut indice_num;
-    unsafe {
-        // Create Vertex Array Object
-        gl::GenVertexArrays(1, &mut vao);
-        try!(glutil::check_error());
-        gl::BindVertexArray(vao);
-        try!(glutil::check_error());
-        // Create a Vertex Buffer Object and copy the vertex data to it
-        gl::GenBuffers(1, &mut vbo);
-        try!(glutil::check_error());
-        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-        try!(glutil::check_error());
-        let vertice_size = mem::size_of::<Vertex>();
-        let vertice_num = vertices.len();
-        let float_size = mem::size_of::<GLfloat>();
-        // println!("Vertex size: {}", vertice_size);
-        // println!("Vertex num: {}", vertice_num);
-        // println!("float size: {}", float_size);
-        gl::BufferData(gl::ARRAY_BUFFER,
-            (vertice_num * vertice_size) as GLsizeiptr,
-            mem::transmute(&vertices[0]), gl::STATIC_DRAW);
-        try!(glutil::check_error());
-
-        indice_num = vertice_num as i32;
-
-        // values taken from layout location in vertex shader
-        let pos_location = 0;
-        let diffuse_location = 1;
-        let stride = vertice_size as i32;
-        gl::EnableVertexAttribArray(pos_location);
-        try!(glutil::check_error());
-        gl::EnableVertexAttribArray(diffuse_location);
-        try!(glutil::check_error());
-        gl::VertexAttribPointer(pos_location, 3, gl::FLOAT, gl::FALSE as GLboolean, stride, ptr::null());
-        try!(glutil::check_error());
-        let diffuse_offset = mem::transmute(float_size * 3); // diffuse comes after position
-        gl::VertexAttribPointer(diffuse_location, 4, gl::FLOAT, gl::FALSE as GLboolean, stride, diffuse_offset);
-        try!(glutil::check_error());
-        gl::BindVertexArray(0);
+/// Ray along -z with a small cross where it starts, drawn with LINES
+fn lines() -> gizmo::Lines {
+    let size = 0.03;
+    let segments = [
+        (Vector3::new(-size, 0.0, 0.0), Vector3::new(size, 0.0, 0.0)),
+        (Vector3::new(0.0, -size, 0.0), Vector3::new(0.0, size, 0.0)),
+        (Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -0.3)),
+    ];
+    let mut m = mesh::IndexedMesh::new();
+    for &(a, b) in segments.iter() {
+        let v0 = m.push_vertex(gizmo::Vertex::new(a));
+        let v1 = m.push_vertex(gizmo::Vertex::new(b));
+        m.push_index(v0);
+        m.push_index(v1);
     }
-    Ok((vao, vbo, indice_num))
+    gizmo::Lines { mesh: m, primitive: gl::LINES }
 }
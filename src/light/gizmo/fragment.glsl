@@ -0,0 +1,8 @@
+#include "common/header.glsl"
+#include "common/material.glsl"
+
+out vec4 pix_color;
+
+void main() {
+    pix_color = material.base_color;
+}
@@ -0,0 +1,135 @@
+extern crate gl;
+extern crate cgmath;
+
+use std::cmp;
+use std::f32::consts;
+use std::num::FloatMath;
+use gl::types::GLenum;
+use cgmath::{Matrix,Matrix4,Vector3,EuclideanVector};
+use light;
+use glutil;
+use glutil::reload;
+use glutil::state;
+use material;
+use mesh;
+use game;
+
+/// Lines that show where a light is, drawn in a flat color
+pub struct Gizmo {
+    program: reload::ReloadableProgram,
+    mesh: mesh::Mesh<Vertex>,
+    material: material::Material,
+}
+
+#[repr(C)]
+pub struct Vertex {
+    pub position: cgmath::Vector3<f32>,
+}
+
+impl Vertex {
+    pub fn new(pos: cgmath::Vector3<f32>) -> Vertex {
+        Vertex {
+            position : pos,
+        }
+    }
+}
+
+impl mesh::VertexLayout for Vertex {
+    fn attributes() -> Vec<mesh::Attribute> {
+        // locations taken from layout location in vertex shader
+        vec![
+            mesh::Attribute::new(0, 3, 0),
+        ]
+    }
+}
+
+/// The line mesh of a gizmo in its own model space
+pub struct Lines {
+    pub mesh: mesh::IndexedMesh<Vertex>,
+    /// mode the lines are drawn with, such as LINE_LOOP
+    pub primitive: GLenum,
+}
+
+impl Gizmo {
+    pub fn new(mode: &reload::SourceMode, lines: &Lines) -> Result<Gizmo, glutil::GlError> {
+        let prog = try!(reload::ReloadableProgram::new(mode,
+            reload::ShaderFile::new("light/gizmo/vertex.glsl", include_str!("vertex.glsl")),
+            reload::ShaderFile::new("light/gizmo/fragment.glsl", include_str!("fragment.glsl"))));
+        let m = try!(mesh::Mesh::new(&lines.mesh, lines.primitive));
+        Ok(Gizmo {
+            program : prog,
+            mesh : m,
+            material : material::Material::unlit(color()),
+        })
+    }
+
+    pub fn reload_if_changed(&mut self) {
+        self.program.reload_if_changed();
+    }
+
+    pub fn draw(&self, ctx: &game::DrawContext, model: &cgmath::Matrix4<f32>) -> Result<(), glutil::GlError> {
+        let mvp = ctx.camera.view_projection().mul_m(model);
+        try!(self.program.set_uniform("mvp", &mvp));
+        try!(self.material.bind(&*self.program));
+
+        // the gizmo is made of flat lines so it must not be culled
+        try!(ctx.apply(&state::RenderState::double_sided()));
+        try!(self.program.bind());
+        try!(self.mesh.draw());
+        self.program.unbind();
+        Ok(())
+    }
+}
+
+/// Color every gizmo is drawn in
+pub fn color() -> cgmath::Vector4<f32> {
+    cgmath::Vector4::new(1.0, 0.0, 0.0, 1.0)
+}
+
+/// Model matrix that places a gizmo at translation with its local z axis along z
+pub fn facing(translation: &Vector3<f32>, z: &Vector3<f32>) -> Matrix4<f32> {
+    let z = z.normalize();
+    let x = light::up_vector(&z).cross(&z).normalize();
+    let y = z.cross(&x);
+    let t = translation;
+    Matrix4::new(
+        x.x, x.y, x.z, 0.0,
+        y.x, y.y, y.z, 0.0,
+        z.x, z.y, z.z, 0.0,
+        t.x, t.y, t.z, 1.0)
+}
+
+/// Circle in the xy plane, drawn with LINE_LOOP
+pub fn circle(radius: f32, div: uint) -> mesh::IndexedMesh<Vertex> {
+    let rad_per_div = consts::PI_2 / div as f32;
+    let mut m = mesh::IndexedMesh::new();
+    for i in range(0, div) {
+        let cur_rad = rad_per_div * i as f32;
+        let x = cur_rad.cos() * radius;
+        let y = cur_rad.sin() * radius;
+        let v = m.push_vertex(Vertex::new(cgmath::Vector3::new(x,y,0.0)));
+        m.push_index(v);
+    }
+    m
+}
+
+/// Cone with the apex at the origin opening towards -z, with a radius of 1 at z = -1.
+/// Made of the base circle and a few lines to the apex, drawn with LINES
+pub fn cone(div: uint) -> mesh::IndexedMesh<Vertex> {
+    let rad_per_div = consts::PI_2 / div as f32;
+    let mut m = mesh::IndexedMesh::new();
+    let apex = m.push_vertex(Vertex::new(Vector3::new(0.0, 0.0, 0.0)));
+    let base: Vec<u32> = range(0, div).map(|i| {
+        let cur_rad = rad_per_div * i as f32;
+        m.push_vertex(Vertex::new(Vector3::new(cur_rad.cos(), cur_rad.sin(), -1.0)))
+    }).collect();
+    for i in range(0, div) {
+        m.push_index(base[i]);
+        m.push_index(base[(i + 1) % div]);
+        if i % cmp::max(div / 4, 1) == 0 {
+            m.push_index(apex);
+            m.push_index(base[i]);
+        }
+    }
+    m
+}
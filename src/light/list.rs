@@ -0,0 +1,193 @@
+extern crate gl;
+extern crate cgmath;
+
+use std::fmt;
+use std::mem;
+use std::ptr;
+use std::error::Error;
+use gl::types::{GLintptr,GLsizeiptr};
+use cgmath::{Matrix4,Vector3,Vector4};
+
+use glutil;
+use glutil::reload;
+use light::{Light,Params};
+use light::gizmo;
+use game;
+use game::Object;
+use control;
+
+/// Maximum number of lights in a LightList. Must match MAX_LIGHTS of common/light.glsl
+pub static MAX_LIGHTS: uint = 8;
+
+/// Uniform buffer binding point of the Lights block in common/light.glsl
+pub static BINDING: u32 = 0;
+
+/// A light could not be added to a LightList
+#[deriving(Clone, PartialEq, Eq)]
+pub enum LightError {
+    /// The list already holds max lights
+    TooMany { max: uint },
+}
+
+impl Error for LightError {
+    fn description(&self) -> &str {
+        match *self {
+            LightError::TooMany { .. } => "too many lights",
+        }
+    }
+
+    fn detail(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl fmt::Show for LightError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LightError::TooMany { max } => write!(f, "At most {} lights are supported", max),
+        }
+    }
+}
+
+/// Params laid out following the std140 rules, where a vec3 takes up as much as a vec4
+#[repr(C)]
+struct Std140Light {
+    position: Vector4<f32>,
+    color: Vector3<f32>,
+    intensity: f32,
+    attenuation: Vector3<f32>,
+    _pad0: f32,
+    ambient: Vector3<f32>,
+    _pad1: f32,
+    spot_direction: Vector3<f32>,
+    spot_cos_inner: f32,
+    spot_cos_outer: f32,
+    _pad2: [f32, ..3],
+}
+
+impl Std140Light {
+    fn new(p: &Params) -> Std140Light {
+        let (inner, outer) = p.spot_cos;
+        Std140Light {
+            position: p.position,
+            color: p.color,
+            intensity: p.intensity,
+            attenuation: p.attenuation,
+            _pad0: 0.0,
+            ambient: p.ambient,
+            _pad1: 0.0,
+            spot_direction: p.spot_direction,
+            spot_cos_inner: inner,
+            spot_cos_outer: outer,
+            _pad2: [0.0, 0.0, 0.0],
+        }
+    }
+}
+
+/// Every light in the scene. They are uploaded to a uniform buffer once per frame
+/// so all lit objects read the same list.
+/// Only the selected light follows the controls, so each of them can be placed on its own.
+pub struct LightList {
+    lights: Vec<Box<Light + 'static>>,
+    selected: uint,
+    buffer: Option<glutil::Buffer>,
+}
+
+impl LightList {
+    pub fn new() -> LightList {
+        LightList {
+            lights: Vec::new(),
+            selected: 0,
+            buffer: None,
+        }
+    }
+
+    /// Add a light and return its index. Lights are initialized by init, so add them before it
+    pub fn push(&mut self, light: Box<Light + 'static>) -> Result<uint, LightError> {
+        if self.lights.len() >= MAX_LIGHTS {
+            return Err(LightError::TooMany { max: MAX_LIGHTS });
+        }
+        self.lights.push(light);
+        Ok(self.lights.len() - 1)
+    }
+
+    pub fn len(&self) -> uint {
+        self.lights.len()
+    }
+
+    pub fn get_mut(&mut self, index: uint) -> Option<&mut Box<Light + 'static>> {
+        self.lights.get_mut(index)
+    }
+
+    /// Current parameters of every light
+    pub fn params(&self) -> Vec<Params> {
+        self.lights.iter().map(|l| l.params()).collect()
+    }
+
+    /// Lines and model matrix of the gizmo of each light, in the order of the lights
+    pub fn gizmos(&self) -> Vec<(&gizmo::Lines, Matrix4<f32>)> {
+        self.lights.iter().map(|l| (l.gizmo_lines(), l.gizmo_model())).collect()
+    }
+
+    /// The matrix each light renders its shadow map with, in the order of the lights.
+    /// See Light::shadow_view_projection
+    pub fn shadow_view_projections(&self, center: &Vector3<f32>, radius: f32) -> Vec<Option<Matrix4<f32>>> {
+        self.lights.iter().map(|l| l.shadow_view_projection(center, radius)).collect()
+    }
+
+    /// Index of the light that follows the controls
+    pub fn selected(&self) -> uint {
+        self.selected
+    }
+
+    pub fn init(&mut self, mode: &reload::SourceMode) -> Result<(), glutil::GlError> {
+        let buffer = try!(glutil::Buffer::new(gl::UNIFORM_BUFFER));
+        let size = mem::size_of::<[i32, ..4]>() + mem::size_of::<Std140Light>() * MAX_LIGHTS;
+        try!(buffer.set_raw_data(size as GLsizeiptr, ptr::null(), gl::DYNAMIC_DRAW));
+        try!(buffer.bind_base(BINDING));
+        self.buffer = Some(buffer);
+        for l in self.lights.iter_mut() {
+            try!(l.init(mode));
+        }
+        Ok(())
+    }
+
+    /// Copy the current parameters of every light to the uniform buffer.
+    /// Call it before drawing the objects that are lit
+    pub fn upload(&self) -> Result<(), glutil::GlError> {
+        let buffer = match self.buffer {
+            Some(ref b) => b,
+            None => return Err(glutil::GlError::Uninitialized { object: "LightList" }),
+        };
+        // light_count is padded to 16 bytes, since the array of structs after it is aligned to a vec4
+        let count = [self.lights.len() as i32, 0, 0, 0];
+        try!(buffer.set_sub_data(0, count.as_slice()));
+        let block: Vec<Std140Light> = self.params().iter().map(|p| Std140Light::new(p)).collect();
+        try!(buffer.set_sub_data(mem::size_of::<[i32, ..4]>() as GLintptr, block.as_slice()));
+        // another buffer may have taken the binding point in the meantime
+        buffer.bind_base(BINDING)
+    }
+}
+
+impl game::Object for LightList {
+    fn update(&mut self, cs: &control::State, dt: f32) -> Result<(),glutil::GlError> {
+        match cs.selected_light {
+            Some(i) if i < self.lights.len() => self.selected = i,
+            _ => (),
+        }
+        // the others are still updated so their gizmos can reload, but without any input
+        let idle = control::State::new();
+        for (i, l) in self.lights.iter_mut().enumerate() {
+            let input = if i == self.selected { cs } else { &idle };
+            try!(l.update(input, dt));
+        }
+        Ok(())
+    }
+
+    fn draw(&self, ctx: &game::DrawContext) -> Result<(),glutil::GlError> {
+        for l in self.lights.iter() {
+            try!(l.draw(ctx));
+        }
+        Ok(())
+    }
+}
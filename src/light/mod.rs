@@ -1 +1,63 @@
-pub mod directional;
\ No newline at end of file
+extern crate cgmath;
+
+use std::num::Float;
+use cgmath::{Matrix4,Vector3,Vector4,EuclideanVector};
+
+use glutil;
+use glutil::reload;
+use game;
+use light::gizmo;
+
+pub use self::list::{LightList,LightError,MAX_LIGHTS};
+
+pub mod gizmo;
+pub mod list;
+pub mod directional;
+pub mod point;
+pub mod spot;
+
+/// Values of a light as seen by the shaders. Mirrors struct Light of common/light.glsl
+pub struct Params {
+    /// w is 0.0 for a directional light, where xyz is the direction towards the light.
+    /// Otherwise xyz is the position of the light
+    pub position: Vector4<f32>,
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+    /// constant, linear and quadratic factor of the attenuation over distance
+    pub attenuation: Vector3<f32>,
+    /// light that reaches every surface regardless of its direction
+    pub ambient: Vector3<f32>,
+    /// direction a spotlight points at
+    pub spot_direction: Vector3<f32>,
+    /// cosine of the angle from spot_direction where the light starts to fade, and where it ends.
+    /// -1.0 for both makes the light reach every direction
+    pub spot_cos: (f32, f32),
+}
+
+/// A light that objects can be lit by
+pub trait Light: game::Object {
+    fn init(&mut self, mode: &reload::SourceMode) -> Result<(), glutil::GlError>;
+    fn params(&self) -> Params;
+
+    /// Lines that mark the light in the scene. They are built with the light and kept for its lifetime
+    fn gizmo_lines(&self) -> &gizmo::Lines;
+
+    /// Model matrix that places gizmo_lines in the world
+    fn gizmo_model(&self) -> Matrix4<f32>;
+
+    /// View projection matrix the shadow map of this light is rendered with,
+    /// or None when it casts no shadow.
+    /// center and radius bound the part of the scene that casts and receives shadows
+    fn shadow_view_projection(&self, _center: &Vector3<f32>, _radius: f32) -> Option<Matrix4<f32>> {
+        None
+    }
+}
+
+/// An up vector for looking along forward, which must not be parallel to it
+pub fn up_vector(forward: &Vector3<f32>) -> Vector3<f32> {
+    if forward.y.abs() < 0.99 * forward.length() {
+        Vector3::new(0.0, 1.0, 0.0)
+    } else {
+        Vector3::new(1.0, 0.0, 0.0)
+    }
+}
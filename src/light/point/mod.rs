@@ -0,0 +1,102 @@
+extern crate gl;
+extern crate cgmath;
+
+use cgmath::{Matrix4,Vector3,Vector4};
+use glutil;
+use glutil::reload;
+use light;
+use light::gizmo;
+use game;
+use control;
+
+/// Light given off in every direction from a single position, like a bulb.
+/// It gets dimmer with distance by 1 / (constant + linear * d + quadratic * d^2)
+pub struct Light {
+    pub position: Vector3<f32>,
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+    /// light that reaches every surface regardless of its direction
+    pub ambient: Vector3<f32>,
+    /// constant, linear and quadratic factor of the attenuation
+    pub attenuation: Vector3<f32>,
+    lines: gizmo::Lines,
+    gizmo: Option<gizmo::Gizmo>,
+}
+
+impl Light {
+    pub fn new(position: Vector3<f32>) -> Light {
+        Light {
+            position: position,
+            color: Vector3::new(1.0, 1.0, 1.0),
+            intensity: 1.0,
+            ambient: Vector3::new(0.15, 0.15, 0.15),
+            // falls to about half at a distance of 3
+            attenuation: Vector3::new(1.0, 0.1, 0.08),
+            lines: lines(),
+            gizmo: None,
+        }
+    }
+}
+
+impl light::Light for Light {
+    fn init(&mut self, mode: &reload::SourceMode) -> Result<(), glutil::GlError> {
+        self.gizmo = Some(try!(gizmo::Gizmo::new(mode, &self.lines)));
+        Ok(())
+    }
+
+    fn params(&self) -> light::Params {
+        let p = self.position;
+        light::Params {
+            position: Vector4::new(p.x, p.y, p.z, 1.0),
+            color: self.color,
+            intensity: self.intensity,
+            attenuation: self.attenuation,
+            ambient: self.ambient,
+            spot_direction: Vector3::new(0.0, 0.0, 0.0),
+            spot_cos: (-1.0, -1.0),
+        }
+    }
+
+    fn gizmo_lines(&self) -> &gizmo::Lines {
+        &self.lines
+    }
+
+    fn gizmo_model(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(&self.position)
+    }
+}
+
+impl game::Object for Light {
+    fn update(&mut self, cs: &control::State, _dt: f32) -> Result<(),glutil::GlError> {
+        match self.gizmo {
+            Some(ref mut g) => g.reload_if_changed(),
+            None => (),
+        }
+        let delta = 0.01;
+        if cs.move_up {
+            self.position.y += delta;
+        }
+        if cs.move_down {
+            self.position.y += -delta;
+        }
+        if cs.move_left {
+            self.position.x += -delta;
+        }
+        if cs.move_right {
+            self.position.x += delta;
+        }
+        Ok(())
+    }
+
+    fn draw(&self, ctx: &game::DrawContext) -> Result<(),glutil::GlError> {
+        match self.gizmo {
+            Some(ref g) => g.draw(ctx, &Matrix4::from_translation(&self.position)),
+            None => Err(glutil::GlError::Uninitialized { object: "point::Light" }),
+        }
+    }
+}
+
+/// A small circle around the position, drawn with LINE_LOOP
+fn lines() -> gizmo::Lines {
+    gizmo::Lines { mesh: gizmo::circle(0.03, 8), primitive: gl::LINE_LOOP }
+}
@@ -0,0 +1,148 @@
+extern crate gl;
+extern crate cgmath;
+
+use std::num::FloatMath;
+use cgmath::{Matrix,Matrix4,Point,Point3,Vector,Vector3,Vector4,EuclideanVector,rad};
+use glutil;
+use glutil::reload;
+use light;
+use light::gizmo;
+use game;
+use control;
+
+/// Widest cone, as the full angle in radians, that still casts a shadow
+static MAX_SHADOW_FOVY: f32 = 2.8;
+
+/// Light given off from a position into a cone, like a stage light.
+/// Full intensity within inner_angle of the direction, fading out smoothly towards outer_angle
+pub struct Light {
+    pub position: Vector3<f32>,
+    /// the direction the light points at
+    direction: Vector3<f32>,
+    /// angles from the direction in radians
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+    /// light that reaches every surface regardless of its direction
+    pub ambient: Vector3<f32>,
+    /// constant, linear and quadratic factor of the attenuation
+    pub attenuation: Vector3<f32>,
+    lines: gizmo::Lines,
+    gizmo: Option<gizmo::Gizmo>,
+}
+
+impl Light {
+    /// A spotlight at position aimed at target
+    pub fn new(position: Vector3<f32>, target: Vector3<f32>) -> Light {
+        Light {
+            position: position,
+            direction: target.sub_v(&position).normalize(),
+            inner_angle: 0.25,
+            outer_angle: 0.35,
+            color: Vector3::new(1.0, 1.0, 1.0),
+            intensity: 1.0,
+            ambient: Vector3::new(0.0, 0.0, 0.0),
+            attenuation: Vector3::new(1.0, 0.1, 0.08),
+            lines: lines(),
+            gizmo: None,
+        }
+    }
+
+    pub fn direction(&self) -> Vector3<f32> {
+        self.direction
+    }
+
+    pub fn set_direction(&mut self, direction: Vector3<f32>) {
+        self.direction = direction.normalize();
+    }
+}
+
+impl light::Light for Light {
+    fn init(&mut self, mode: &reload::SourceMode) -> Result<(), glutil::GlError> {
+        self.gizmo = Some(try!(gizmo::Gizmo::new(mode, &self.lines)));
+        Ok(())
+    }
+
+    fn params(&self) -> light::Params {
+        let p = self.position;
+        // the fade needs the outer edge to be outside of the inner one
+        let outer = self.outer_angle.max(self.inner_angle);
+        light::Params {
+            position: Vector4::new(p.x, p.y, p.z, 1.0),
+            color: self.color,
+            intensity: self.intensity,
+            attenuation: self.attenuation,
+            ambient: self.ambient,
+            spot_direction: self.direction,
+            spot_cos: (self.inner_angle.cos(), outer.cos()),
+        }
+    }
+
+    fn gizmo_lines(&self) -> &gizmo::Lines {
+        &self.lines
+    }
+
+    fn gizmo_model(&self) -> Matrix4<f32> {
+        gizmo_model(self)
+    }
+
+    fn shadow_view_projection(&self, center: &Vector3<f32>, radius: f32) -> Option<Matrix4<f32>> {
+        let eye = Point3::from_vec(&self.position);
+        let view = Matrix4::look_at(&eye, &eye.add_v(&self.direction), &light::up_vector(&self.direction));
+        // the frustum covers the cone, and reaches past everything in the scene.
+        // wider cones would spread the shadow map too thin, and a perspective can't reach PI
+        let fovy = self.outer_angle.max(self.inner_angle) * 2.0;
+        if fovy <= 0.0 || fovy > MAX_SHADOW_FOVY {
+            return None;
+        }
+        let far = center.sub_v(&self.position).length() + radius * 2.0;
+        let projection = cgmath::perspective(rad(fovy), 1.0, 0.05, far);
+        Some(projection.mul_m(&view))
+    }
+}
+
+impl game::Object for Light {
+    fn update(&mut self, cs: &control::State, _dt: f32) -> Result<(),glutil::GlError> {
+        match self.gizmo {
+            Some(ref mut g) => g.reload_if_changed(),
+            None => (),
+        }
+        let delta = 0.01;
+        if cs.move_up {
+            self.position.y += delta;
+        }
+        if cs.move_down {
+            self.position.y += -delta;
+        }
+        if cs.move_left {
+            self.position.x += -delta;
+        }
+        if cs.move_right {
+            self.position.x += delta;
+        }
+        Ok(())
+    }
+
+    fn draw(&self, ctx: &game::DrawContext) -> Result<(),glutil::GlError> {
+        let g = match self.gizmo {
+            Some(ref g) => g,
+            None => return Err(glutil::GlError::Uninitialized { object: "spot::Light" }),
+        };
+        g.draw(ctx, &gizmo_model(self))
+    }
+}
+
+/// Outline of the cone, drawn with LINES
+fn lines() -> gizmo::Lines {
+    gizmo::Lines { mesh: gizmo::cone(16), primitive: gl::LINES }
+}
+
+/// The unit cone opens towards -z, so it is turned away from the direction
+/// and widened to the outer angle
+fn gizmo_model(l: &Light) -> Matrix4<f32> {
+    let length = 0.3;
+    let radius = length * l.outer_angle.max(l.inner_angle).tan();
+    let scale = Matrix4::from_diagonal(&Vector4::new(radius, radius, length, 1.0));
+    gizmo::facing(&l.position, &l.direction.mul_s(-1.0)).mul_m(&scale)
+}
@@ -1,28 +1,115 @@
 extern crate glutin;
-extern crate libc;
 extern crate gl;
+extern crate time;
 extern crate cgmath;
+extern crate christmas_star;
 
-mod game;
-mod glutil;
-mod christmas_star;
-mod light;
-mod control;
+use christmas_star::{camera,christmas_star,control,glutil,offscreen,png,scene,software};
+use christmas_star::game::Object;
 
-fn clear_screen() {
-    unsafe { 
-        gl::ClearColor(1.0, 1.0, 1.0, 1.0);
-        gl::Clear(gl::COLOR_BUFFER_BIT);
+/// What draws the frames
+#[deriving(PartialEq)]
+enum Backend {
+    /// OpenGL in a window
+    Gl,
+    /// the CPU rasterizer of christmas_star::software, which only saves screenshots
+    Software,
+}
+
+/// Command line options
+struct Options {
+    backend: Backend,
+    /// load the shaders from the source tree and reload them when they are saved
+    hot_reload: bool,
+    /// put a wall behind the star to catch its shadow
+    backdrop: bool,
+    /// keep the background of screenshots transparent
+    alpha: bool,
+    /// render frames and save the last one here instead of running until the window is closed
+    screenshot: Option<Path>,
+    frames: uint,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Options, String> {
+        let mut options = Options {
+            backend: Backend::Gl,
+            hot_reload: false,
+            backdrop: false,
+            alpha: false,
+            screenshot: None,
+            frames: 1,
+        };
+        let mut it = args.iter().skip(1);
+        loop {
+            let arg = match it.next() {
+                Some(a) => a.as_slice(),
+                None => break,
+            };
+            match arg {
+                "--backend" => match it.next().map(|b| b.as_slice()) {
+                    Some("gl") => options.backend = Backend::Gl,
+                    Some("software") => options.backend = Backend::Software,
+                    _ => return Err("--backend needs gl or software".to_string()),
+                },
+                "--hot-reload" => options.hot_reload = true,
+                "--backdrop" => options.backdrop = true,
+                "--alpha" => options.alpha = true,
+                "--screenshot" => match it.next() {
+                    Some(path) => options.screenshot = Some(Path::new(path.as_slice())),
+                    None => return Err("--screenshot needs a file name".to_string()),
+                },
+                "--frames" => match it.next().and_then(|n| from_str::<uint>(n.as_slice())) {
+                    Some(n) if n > 0 => options.frames = n,
+                    _ => return Err("--frames needs a number of frames above 0".to_string()),
+                },
+                _ => return Err(format!("unknown option {}", arg)),
+            }
+        }
+        if options.backend == Backend::Software && options.screenshot.is_none() {
+            return Err("the software backend has no window, so it needs --screenshot".to_string());
+        }
+        Ok(options)
     }
 }
 
-fn process_main_loop(window: &glutin::Window, obj_list: &mut Vec<&mut game::Object>) {
+/// Save the frame just drawn into the window as a PNG file. Must be called before the buffers are swapped.
+/// With alpha the scene is drawn again offscreen on a transparent background,
+/// since the window has nothing to show through it
+fn save_screenshot(window: &glutin::Window, scene: &mut scene::Scene, path: &Path, alpha: bool) {
+    let (w, h) = window.get_inner_size().unwrap_or((300, 300));
+    let image = if alpha {
+        let image = offscreen::capture(scene, w as u32, h as u32, true);
+        // the capture drew into its own framebuffer, so go back to the window size
+        scene.set_viewport(w as u32, h as u32);
+        image
+    } else {
+        offscreen::read_pixels(w as u32, h as u32)
+    };
+    let image = image.unwrap_or_else(|e| panic!("Error when capturing: {}", e));
+    png::write(path, &image, alpha)
+        .unwrap_or_else(|e| panic!("Error when saving {}: {}", path.display(), e));
+    println!("Saved {}", path.display());
+}
+
+fn process_main_loop(window: &glutin::Window, scene: &mut scene::Scene, options: &Options) {
     let mut cs = control::State::new(); 
+    let mut orbit = camera::OrbitController::new(&scene.camera);
+    match window.get_inner_size() {
+        Some((w, h)) => scene.set_viewport(w as u32, h as u32),
+        None => (),
+    }
+    let mut last_time = time::precise_time_ns();
+    let mut frame = 0u;
     while !window.is_closed() {
         // process window evets
         for ev in window.poll_events() {
             match ev {
                 glutin::Event::KeyboardInput(elem_state, _, key_code) => cs.handle_key_input(elem_state, key_code),
+                glutin::Event::MouseInput(elem_state, button) => cs.handle_mouse_input(elem_state, button),
+                glutin::Event::MouseMoved(position) => cs.handle_mouse_moved(position),
+                glutin::Event::MouseWheel(delta) => cs.handle_mouse_wheel(delta),
+                glutin::Event::Resized(w, h) => scene.set_viewport(w as u32, h as u32),
                 _ => (),
             }
         }
@@ -33,40 +120,94 @@ fn process_main_loop(window: &glutin::Window, obj_list: &mut Vec<&mut game::Obje
         // We should be checking the elapsed time to see how long we can wait here.
         std::io::timer::sleep(std::time::duration::Duration::milliseconds(8));
 
+        let now = time::precise_time_ns();
+        // a fixed step makes the frames of --screenshot the same on every run
+        let dt = match options.screenshot {
+            Some(_) => 1.0 / 60.0,
+            None => (now - last_time) as f32 * 1e-9,
+        };
+        last_time = now;
+
         // update all
-        for o in obj_list.iter_mut() {
-            o.update(&cs)
-                .unwrap_or_else(|e| panic!("Error when updating: {}", e));
-        }
+        scene.update(&cs, dt)
+            .unwrap_or_else(|e| panic!("Error when updating: {}", e));
+        orbit.update(&cs, &mut scene.camera, dt);
+        let screenshot_key = cs.screenshot;
+        cs.end_frame();
 
         // draw all
-        clear_screen();
-        for o in obj_list.iter() {
-            o.draw()
-                .unwrap_or_else(|e| panic!("Error when drawing: {}", e));
+        scene.draw()
+            .unwrap_or_else(|e| panic!("Error when drawing: {}", e));
+        frame += 1;
+
+        // screenshots read the back buffer, so they are taken before it is swapped
+        let last_frame = match options.screenshot {
+            Some(ref path) if frame >= options.frames => {
+                save_screenshot(window, scene, path, options.alpha);
+                true
+            },
+            _ => false,
+        };
+        if screenshot_key {
+            // milliseconds keep shots taken within the same second apart
+            let t = time::get_time();
+            let path = Path::new(format!("screenshot-{}{:03}.png", t.sec, t.nsec / 1000000));
+            save_screenshot(window, scene, &path, options.alpha);
+        }
+        if last_frame {
+            return;
         }
         unsafe { gl::Flush(); }
         window.swap_buffers();
     }
 }
 
+/// Render the star on the CPU and save the last frame, without creating a window or a GL context
+fn render_software(options: &Options, path: &Path) {
+    let mut lights = scene::default_lights();
+    let mut star = scene::default_star();
+    // the CPU only does Blinn-Phong, so the preview matches the GL output of the same shading
+    star.set_shading(christmas_star::Shading::BlinnPhong);
+    let cs = control::State::new();
+    for _ in range(0, options.frames) {
+        lights.update(&cs, 1.0 / 60.0)
+            .and_then(|_| star.update(&cs, 1.0 / 60.0))
+            .unwrap_or_else(|e| panic!("Error when updating: {}", e));
+    }
+    let mut camera = camera::Camera::new();
+    let clear_alpha = if options.alpha { 0.0 } else { 1.0 };
+    let image = software::render(&star, &lights, &mut camera, 300, 300,
+        cgmath::Vector4::new(1.0, 1.0, 1.0, clear_alpha))
+        .unwrap_or_else(|e| panic!("Error when rendering: {}", e));
+    png::write(path, &image, options.alpha)
+        .unwrap_or_else(|e| panic!("Error when saving {}: {}", path.display(), e));
+    println!("Saved {}", path.display());
+}
+
 fn main() {
+    let options = Options::parse(std::os::args().as_slice())
+        .unwrap_or_else(|e| panic!("{}\nusage: main [--backend gl|software] [--hot-reload] [--backdrop] [--alpha] [--screenshot out.png [--frames N]]", e));
+    match (&options.backend, &options.screenshot) {
+        (&Backend::Software, &Some(ref path)) => return render_software(&options, path),
+        _ => (),
+    }
     let builder = glutin::WindowBuilder::new();
     let r = builder.with_dimensions(300, 300)
+        .with_depth_buffer(24)
         .with_title("rust glsl sample".to_string())
         .build();
     let window = r.unwrap_or_else(|e| panic!("Error while building window: {}", e));
     unsafe { window.make_current() };
     gl::load_with(|symbol| window.get_proc_address(symbol));
 
-    let mut obj = christmas_star::ChristmasStar::new();
-    obj.init()
-        .unwrap_or_else(|e| panic!("ChristmasStar init failed: {}", e));
-    // need an indent here because obj_list will own the obj
-    {
-        let mut obj_list : Vec<&mut game::Object> = Vec::new();
-        obj_list.push(&mut obj);
-        process_main_loop(&window, &mut obj_list);
-    }
-    obj.close();
-}
\ No newline at end of file
+    let shader_mode = if options.hot_reload {
+        // the sources are found from wherever the binary runs
+        glutil::reload::SourceMode::Disk(Path::new(env!("CARGO_MANIFEST_DIR")).join("src"))
+    } else {
+        glutil::reload::SourceMode::Embedded
+    };
+
+    let mut scene = scene::Scene::christmas(&shader_mode, options.backdrop)
+        .unwrap_or_else(|e| panic!("Scene init failed: {}", e));
+    process_main_loop(&window, &mut scene, &options);
+}
@@ -0,0 +1,95 @@
+extern crate cgmath;
+
+use cgmath::{Vector3,Vector4};
+
+use glutil;
+
+/// Surface properties of an object, bound to the `material` uniform of common/material.glsl.
+/// Blinn-Phong shading uses specular and shininess, while metallic and roughness are for
+/// physically based shading. Colors are sRGB encoded.
+#[deriving(Clone)]
+pub struct Material {
+    /// color of the surface. Alpha below 1.0 makes it translucent
+    pub base_color: Vector4<f32>,
+    pub specular: Vector3<f32>,
+    pub shininess: f32,
+    pub metallic: f32,
+    pub roughness: f32,
+    /// light given off by the surface itself
+    pub emissive: Vector3<f32>,
+}
+
+impl Material {
+    pub fn gold() -> Material {
+        Material {
+            // reflectance of gold, converted from linear (1.0, 0.71, 0.29)
+            base_color: Vector4::new(1.0, 0.86, 0.57, 1.0),
+            specular: Vector3::new(1.0, 0.9, 0.6),
+            shininess: 64.0,
+            metallic: 1.0,
+            roughness: 0.25,
+            emissive: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn silver() -> Material {
+        Material {
+            base_color: Vector4::new(0.97, 0.96, 0.91, 1.0),
+            specular: Vector3::new(1.0, 1.0, 1.0),
+            shininess: 96.0,
+            metallic: 1.0,
+            roughness: 0.2,
+            emissive: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn glass() -> Material {
+        Material {
+            base_color: Vector4::new(0.8, 0.9, 1.0, 0.35),
+            specular: Vector3::new(1.0, 1.0, 1.0),
+            shininess: 128.0,
+            metallic: 0.0,
+            roughness: 0.05,
+            emissive: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn matte_paper() -> Material {
+        Material {
+            base_color: Vector4::new(0.85, 0.82, 0.75, 1.0),
+            specular: Vector3::new(0.05, 0.05, 0.05),
+            shininess: 4.0,
+            metallic: 0.0,
+            roughness: 0.9,
+            emissive: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    /// A material that ignores lighting and is drawn with color as is
+    pub fn unlit(color: Vector4<f32>) -> Material {
+        Material {
+            base_color: color,
+            specular: Vector3::new(0.0, 0.0, 0.0),
+            shininess: 1.0,
+            metallic: 0.0,
+            roughness: 1.0,
+            emissive: Vector3::new(color.x, color.y, color.z),
+        }
+    }
+
+    pub fn is_translucent(&self) -> bool {
+        self.base_color.w < 1.0
+    }
+
+    /// Set the material uniforms of program.
+    /// Members the shader doesn't use are optimized out by the driver, so they are skipped
+    pub fn bind(&self, program: &glutil::Program) -> Result<(), glutil::GlError> {
+        try!(program.set_optional_uniform("material.base_color", &self.base_color));
+        try!(program.set_optional_uniform("material.specular", &self.specular));
+        try!(program.set_optional_uniform("material.shininess", &self.shininess));
+        try!(program.set_optional_uniform("material.metallic", &self.metallic));
+        try!(program.set_optional_uniform("material.roughness", &self.roughness));
+        try!(program.set_optional_uniform("material.emissive", &self.emissive));
+        Ok(())
+    }
+}
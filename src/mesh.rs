@@ -0,0 +1,201 @@
+extern crate gl;
+extern crate libc;
+
+use gl::types::{GLenum,GLuint,GLint,GLsizei,GLsizeiptr,GLboolean};
+use std::mem;
+use std::ptr;
+use std::u16;
+
+use glutil;
+
+/// Indices of a mesh.
+/// u16 is used while all vertices can be addressed by it, and switches to u32 once they can't.
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    pub fn new() -> Indices {
+        Indices::U16(Vec::new())
+    }
+
+    pub fn len(&self) -> uint {
+        match *self {
+            Indices::U16(ref v) => v.len(),
+            Indices::U32(ref v) => v.len(),
+        }
+    }
+
+    pub fn get(&self, i: uint) -> u32 {
+        match *self {
+            Indices::U16(ref v) => v[i] as u32,
+            Indices::U32(ref v) => v[i],
+        }
+    }
+
+    pub fn push(&mut self, index: u32) {
+        if index > u16::MAX as u32 {
+            self.widen();
+        }
+        match *self {
+            Indices::U16(ref mut v) => v.push(index as u16),
+            Indices::U32(ref mut v) => v.push(index),
+        }
+    }
+
+    /// Type of a single index passed to glDrawElements
+    pub fn gl_type(&self) -> GLenum {
+        match *self {
+            Indices::U16(_) => gl::UNSIGNED_SHORT,
+            Indices::U32(_) => gl::UNSIGNED_INT,
+        }
+    }
+
+    pub fn byte_size(&self) -> GLsizeiptr {
+        let size = match *self {
+            Indices::U16(ref v) => v.len() * mem::size_of::<u16>(),
+            Indices::U32(ref v) => v.len() * mem::size_of::<u32>(),
+        };
+        size as GLsizeiptr
+    }
+
+    pub fn as_ptr(&self) -> *const libc::c_void {
+        match *self {
+            Indices::U16(ref v) => v.as_ptr() as *const libc::c_void,
+            Indices::U32(ref v) => v.as_ptr() as *const libc::c_void,
+        }
+    }
+
+    fn widen(&mut self) {
+        let wide = match *self {
+            Indices::U16(ref v) => v.iter().map(|&i| i as u32).collect(),
+            Indices::U32(_) => return,
+        };
+        *self = Indices::U32(wide);
+    }
+}
+
+/// Vertices and the indices that build primitives from them.
+/// Vertices are never merged, so a position that needs a different normal per face
+/// (flat shading) is simply pushed once per face.
+pub struct IndexedMesh<V> {
+    pub vertices: Vec<V>,
+    pub indices: Indices,
+}
+
+impl<V> IndexedMesh<V> {
+    pub fn new() -> IndexedMesh<V> {
+        IndexedMesh {
+            vertices: Vec::new(),
+            indices: Indices::new(),
+        }
+    }
+
+    /// Add a vertex and return its index
+    pub fn push_vertex(&mut self, v: V) -> u32 {
+        self.vertices.push(v);
+        (self.vertices.len() - 1) as u32
+    }
+
+    pub fn push_index(&mut self, index: u32) {
+        self.indices.push(index);
+    }
+
+    pub fn push_triangle(&mut self, i0: u32, i1: u32, i2: u32) {
+        self.indices.push(i0);
+        self.indices.push(i1);
+        self.indices.push(i2);
+    }
+
+    /// Add two triangles sharing the i0-i2 diagonal
+    pub fn push_quad(&mut self, i0: u32, i1: u32, i2: u32, i3: u32) {
+        self.push_triangle(i0, i1, i2);
+        self.push_triangle(i0, i2, i3);
+    }
+}
+
+/// A float vertex attribute and where it lives within a vertex
+pub struct Attribute {
+    /// layout location in the vertex shader
+    pub location: GLuint,
+    /// number of floats
+    pub components: GLint,
+    /// offset in bytes from the start of the vertex
+    pub offset: uint,
+}
+
+impl Attribute {
+    pub fn new(location: GLuint, components: GLint, offset: uint) -> Attribute {
+        Attribute {
+            location: location,
+            components: components,
+            offset: offset,
+        }
+    }
+}
+
+/// A vertex type that can be uploaded to a vertex buffer.
+/// Implementors should be #[repr(C)] so the offsets match the actual memory layout.
+pub trait VertexLayout {
+    fn attributes() -> Vec<Attribute>;
+}
+
+/// An IndexedMesh uploaded to the GPU. The GL objects are deleted when dropped
+pub struct Mesh<V> {
+    vao: glutil::VertexArray,
+    // kept to tie the lifetime of the buffers to the VAO referencing them
+    #[allow(dead_code)]
+    vbo: glutil::Buffer,
+    #[allow(dead_code)]
+    ebo: glutil::Buffer,
+    index_num: i32,
+    index_type: GLenum,
+    primitive: GLenum,
+}
+
+impl<V: VertexLayout> Mesh<V> {
+    /// Upload the vertices and indices. primitive is the mode passed to glDrawElements
+    pub fn new(m: &IndexedMesh<V>, primitive: GLenum) -> Result<Mesh<V>, glutil::GlError> {
+        let vao = try!(glutil::VertexArray::new());
+        try!(vao.bind());
+        // Create a Vertex Buffer Object and copy the vertex data to it
+        let vbo = try!(glutil::Buffer::new(gl::ARRAY_BUFFER));
+        try!(vbo.set_data(m.vertices.as_slice(), gl::STATIC_DRAW));
+        // Create an Element Buffer Object and copy the indices to it.
+        // The binding is recorded in the VAO so it must stay bound until the VAO is unbound
+        let ebo = try!(glutil::Buffer::new(gl::ELEMENT_ARRAY_BUFFER));
+        try!(ebo.set_raw_data(m.indices.byte_size(), m.indices.as_ptr(), gl::STATIC_DRAW));
+
+        let stride = mem::size_of::<V>() as GLsizei;
+        for a in V::attributes().iter() {
+            unsafe {
+                gl::EnableVertexAttribArray(a.location);
+                try!(glutil::check_error("glEnableVertexAttribArray"));
+                gl::VertexAttribPointer(a.location, a.components, gl::FLOAT, gl::FALSE as GLboolean,
+                    stride, a.offset as *const libc::c_void);
+                try!(glutil::check_error("glVertexAttribPointer"));
+            }
+        }
+        vao.unbind();
+        Ok(Mesh {
+            vao: vao,
+            vbo: vbo,
+            ebo: ebo,
+            index_num: m.indices.len() as i32,
+            index_type: m.indices.gl_type(),
+            primitive: primitive,
+        })
+    }
+
+    /// Draw with the currently used program
+    pub fn draw(&self) -> Result<(), glutil::GlError> {
+        try!(self.vao.bind());
+        unsafe {
+            gl::DrawElements(self.primitive, self.index_num, self.index_type, ptr::null());
+        }
+        try!(glutil::check_error("glDrawElements"));
+        self.vao.unbind();
+        Ok(())
+    }
+}
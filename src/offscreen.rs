@@ -0,0 +1,157 @@
+extern crate gl;
+extern crate glutin;
+extern crate libc;
+
+use gl::types::GLsizei;
+
+use glutil;
+use glutil::framebuffer;
+use glutil::framebuffer::{Framebuffer,Renderbuffer};
+use scene;
+
+/// Pixels read back from a framebuffer
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// 4 bytes of RGBA per pixel. Rows go from the top to the bottom of the image
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// A width x height image filled with transparent black
+    pub fn new(width: u32, height: u32) -> Image {
+        Image {
+            width: width,
+            height: height,
+            pixels: Vec::from_elem((width * height * 4) as uint, 0u8),
+        }
+    }
+
+    /// RGBA of the pixel x from the left and y from the top
+    pub fn pixel(&self, x: u32, y: u32) -> [u8, ..4] {
+        let i = ((y * self.width + x) * 4) as uint;
+        let p = self.pixels.slice(i, i + 4);
+        [p[0], p[1], p[2], p[3]]
+    }
+}
+
+/// A framebuffer object with a color and depth buffer of any size, to render without a window
+pub struct RenderTarget {
+    width: u32,
+    height: u32,
+    framebuffer: Framebuffer,
+    // kept alive while they are attached to the framebuffer
+    #[allow(dead_code)]
+    color: Renderbuffer,
+    #[allow(dead_code)]
+    depth: Renderbuffer,
+}
+
+impl RenderTarget {
+    pub fn new(width: u32, height: u32) -> Result<RenderTarget, glutil::GlError> {
+        let (w, h) = (width as GLsizei, height as GLsizei);
+        let color = try!(Renderbuffer::new(gl::RGBA8, w, h));
+        let depth = try!(Renderbuffer::new(gl::DEPTH_COMPONENT24, w, h));
+        let previous = framebuffer::bound();
+        let framebuffer = try!(Framebuffer::new());
+        let status = framebuffer.attach_renderbuffer(gl::COLOR_ATTACHMENT0, &color)
+            .and_then(|_| framebuffer.attach_renderbuffer(gl::DEPTH_ATTACHMENT, &depth))
+            .and_then(|_| framebuffer.check_status());
+        framebuffer::restore(previous);
+        try!(status);
+        Ok(RenderTarget {
+            width: width,
+            height: height,
+            framebuffer: framebuffer,
+            color: color,
+            depth: depth,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Draw into the target until unbind is called
+    pub fn bind(&self) -> Result<(), glutil::GlError> {
+        self.framebuffer.bind()
+    }
+
+    pub fn unbind(&self) {
+        self.framebuffer.unbind();
+    }
+
+    /// Wait for the drawing to finish and read back the color buffer
+    pub fn read_pixels(&self) -> Result<Image, glutil::GlError> {
+        let previous = framebuffer::bound();
+        try!(self.bind());
+        let image = read_pixels(self.width, self.height);
+        framebuffer::restore(previous);
+        image
+    }
+}
+
+/// Read the color of the bottom left width x height pixels of the bound framebuffer.
+/// GL returns the rows from the bottom, so they are flipped to go from the top
+pub fn read_pixels(width: u32, height: u32) -> Result<Image, glutil::GlError> {
+    let row = (width * 4) as uint;
+    let mut raw = Vec::from_elem(row * height as uint, 0u8);
+    unsafe {
+        gl::Finish();
+        // rows are tightly packed, whatever the width
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(0, 0, width as GLsizei, height as GLsizei, gl::RGBA, gl::UNSIGNED_BYTE,
+            raw.as_mut_ptr() as *mut libc::c_void);
+    }
+    try!(glutil::check_error("glReadPixels"));
+    let mut pixels = Vec::with_capacity(raw.len());
+    for y in range(0, height as uint).rev() {
+        pixels.push_all(raw.slice(y * row, (y + 1) * row));
+    }
+    Ok(Image {
+        width: width,
+        height: height,
+        pixels: pixels,
+    })
+}
+
+/// Create an OpenGL context that has no window and make it current.
+/// On Linux this goes through OSMesa, so it works on machines without a GPU or a display.
+/// The context must be kept alive while rendering
+pub fn create_context(width: u32, height: u32) -> Result<glutin::HeadlessContext, glutil::GlError> {
+    let context = match glutin::HeadlessRendererBuilder::new(width, height).build() {
+        Ok(c) => c,
+        Err(_) => return Err(glutil::GlError::Creation { resource: "headless context" }),
+    };
+    unsafe { context.make_current() };
+    gl::load_with(|symbol| context.get_proc_address(symbol));
+    Ok(context)
+}
+
+/// Render a single frame of scene into a width x height image
+pub fn render(scene: &mut scene::Scene, width: u32, height: u32) -> Result<Image, glutil::GlError> {
+    let target = try!(RenderTarget::new(width, height));
+    let previous = framebuffer::bound();
+    try!(target.bind());
+    scene.set_viewport(width, height);
+    let drawn = scene.draw();
+    framebuffer::restore(previous);
+    try!(drawn);
+    target.read_pixels()
+}
+
+/// Render scene like render, but clear to a transparent background when alpha is true
+/// so only what was drawn is opaque
+pub fn capture(scene: &mut scene::Scene, width: u32, height: u32, alpha: bool) -> Result<Image, glutil::GlError> {
+    let clear_color = scene.clear_color;
+    if alpha {
+        scene.clear_color.w = 0.0;
+    }
+    let image = render(scene, width, height);
+    scene.clear_color = clear_color;
+    image
+}
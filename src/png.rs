@@ -0,0 +1,217 @@
+extern crate flate;
+
+use std::io::{File,IoResult};
+use std::mem;
+use std::num::SignedInt;
+
+use offscreen::Image;
+
+static SIGNATURE: [u8, ..8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// Encode image as a PNG file.
+/// The alpha channel is dropped unless alpha is true, which keeps pixels that were
+/// cleared with a transparent color see-through
+pub fn encode(image: &Image, alpha: bool) -> Vec<u8> {
+    let channels = if alpha { 4 } else { 3 };
+    // every row starts with its filter type, and 0 leaves the row as is
+    let mut raw = Vec::with_capacity((image.width * channels + 1) as uint * image.height as uint);
+    for row in image.pixels.chunks((image.width * 4) as uint) {
+        raw.push(0u8);
+        for p in row.chunks(4) {
+            raw.push_all(p.slice_to(channels as uint));
+        }
+    }
+    let compressed = flate::deflate_bytes_zlib(raw.as_slice()).expect("deflate failed");
+
+    let mut header = Vec::new();
+    push_u32(&mut header, image.width);
+    push_u32(&mut header, image.height);
+    // bit depth, color type (RGB or RGBA), compression, filter and interlace method
+    header.push_all(&[8, if alpha { 6 } else { 2 }, 0, 0, 0]);
+
+    let mut png = Vec::new();
+    png.push_all(&SIGNATURE);
+    push_chunk(&mut png, b"IHDR", header.as_slice());
+    push_chunk(&mut png, b"IDAT", compressed.as_slice());
+    push_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Decode an 8 bit RGB or RGBA PNG file without interlacing, such as the ones written by encode.
+/// RGB is returned with opaque alpha
+pub fn decode(data: &[u8]) -> Result<Image, String> {
+    if data.len() < SIGNATURE.len() || data.slice_to(SIGNATURE.len()) != SIGNATURE.as_slice() {
+        return Err("not a PNG file".to_string());
+    }
+    let mut header = None;
+    let mut compressed = Vec::new();
+    let mut pos = SIGNATURE.len();
+    while pos + 8 <= data.len() {
+        let len = read_u32(data, pos) as uint;
+        let ty = data.slice(pos + 4, pos + 8);
+        if pos + 12 + len > data.len() {
+            return Err("chunk runs past the end of the file".to_string());
+        }
+        let body = data.slice(pos + 8, pos + 8 + len);
+        if read_u32(data, pos + 8 + len) != crc32(ty, body) {
+            return Err(format!("bad CRC in chunk {}", String::from_utf8_lossy(ty)));
+        }
+        match ty {
+            b"IHDR" => {
+                if len < 13 {
+                    return Err("IHDR is too short".to_string());
+                }
+                header = Some((read_u32(body, 0), read_u32(body, 4), body[8], body[9], body[12]));
+            },
+            b"IDAT" => compressed.push_all(body),
+            b"IEND" => break,
+            _ => (),
+        }
+        pos += 12 + len;
+    }
+    let (width, height, channels) = match header {
+        Some((w, h, 8, 2, 0)) => (w, h, 3u),
+        Some((w, h, 8, 6, 0)) => (w, h, 4u),
+        Some(_) => return Err("only 8 bit RGB and RGBA without interlacing are supported".to_string()),
+        None => return Err("missing IHDR".to_string()),
+    };
+    let raw = match flate::inflate_bytes_zlib(compressed.as_slice()) {
+        Some(r) => r,
+        None => return Err("IDAT could not be inflated".to_string()),
+    };
+    let raw = raw.as_slice();
+    let stride = width as uint * channels;
+    if raw.len() < (stride + 1) * height as uint {
+        return Err("image data is too short".to_string());
+    }
+
+    let mut image = Image::new(width, height);
+    let mut previous = Vec::from_elem(stride, 0u8);
+    let mut current = Vec::from_elem(stride, 0u8);
+    for y in range(0, height as uint) {
+        let line = raw.slice((stride + 1) * y, (stride + 1) * (y + 1));
+        let filter = line[0];
+        for x in range(0, stride) {
+            let a = if x >= channels { current[x - channels] } else { 0 };
+            let b = previous[x];
+            let c = if x >= channels { previous[x - channels] } else { 0 };
+            let predicted = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(format!("unknown filter type {}", filter)),
+            };
+            // the filtered byte is the difference to the prediction modulo 256
+            current[x] = ((line[x + 1] as u16 + predicted as u16) & 0xff) as u8;
+        }
+        for x in range(0, width as uint) {
+            let dst = (y * width as uint + x) * 4;
+            for ch in range(0, 4) {
+                image.pixels[dst + ch] = if ch < channels { current[x * channels + ch] } else { 255 };
+            }
+        }
+        mem::swap(&mut previous, &mut current);
+    }
+    Ok(image)
+}
+
+/// Read the PNG file at path. See decode
+pub fn read(path: &Path) -> Result<Image, String> {
+    let data = match File::open(path).read_to_end() {
+        Ok(d) => d,
+        Err(e) => return Err(format!("could not read {}: {}", path.display(), e)),
+    };
+    decode(data.as_slice())
+}
+
+/// Write image to path as a PNG file. See encode
+pub fn write(path: &Path, image: &Image, alpha: bool) -> IoResult<()> {
+    let mut file = try!(File::create(path));
+    file.write(encode(image, alpha).as_slice())
+}
+
+fn push_u32(out: &mut Vec<u8>, v: u32) {
+    out.push_all(&[(v >> 24) as u8, (v >> 16) as u8, (v >> 8) as u8, v as u8]);
+}
+
+fn read_u32(data: &[u8], pos: uint) -> u32 {
+    (data[pos] as u32 << 24) | (data[pos + 1] as u32 << 16) | (data[pos + 2] as u32 << 8) | data[pos + 3] as u32
+}
+
+/// Whichever of left, up and upper left is closest to left + up - upper left
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Append a chunk with its length and the CRC of its type and data
+fn push_chunk(out: &mut Vec<u8>, ty: &[u8], data: &[u8]) {
+    push_u32(out, data.len() as u32);
+    out.push_all(ty);
+    out.push_all(data);
+    let crc = crc32(ty, data);
+    push_u32(out, crc);
+}
+
+/// CRC-32 over a then b, with the polynomial used by PNG and zlib
+fn crc32(a: &[u8], b: &[u8]) -> u32 {
+    let mut crc = 0xffffffffu32;
+    for &byte in a.iter().chain(b.iter()) {
+        crc ^= byte as u32;
+        for _ in range(0u, 8) {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+        }
+    }
+    crc ^ 0xffffffff
+}
+
+#[cfg(test)]
+mod test {
+    extern crate flate;
+
+    use super::{SIGNATURE,decode,push_chunk};
+
+    #[test]
+    fn every_filter_type() {
+        // a 2x5 RGB image with one row per filter type, filtered by hand.
+        // the rows wrap around 256, and the Paeth row picks the left, up and upper left byte
+        let rows: [[u8, ..6], ..5] = [
+            [10, 20, 30, 250, 5, 100],
+            [200, 100, 50, 10, 90, 60],
+            [220, 110, 40, 20, 80, 255],
+            [100, 200, 0, 50, 60, 70],
+            [160, 30, 20, 60, 50, 80],
+        ];
+        let raw: &[u8] = &[
+            0, 10, 20, 30, 250, 5, 100,
+            1, 200, 100, 50, 66, 246, 10,
+            2, 20, 10, 246, 10, 246, 195,
+            3, 246, 145, 236, 246, 176, 199,
+            4, 60, 86, 20, 216, 20, 10,
+        ];
+        let mut png = Vec::new();
+        png.push_all(&SIGNATURE);
+        push_chunk(&mut png, b"IHDR", &[0, 0, 0, 2, 0, 0, 0, 5, 8, 2, 0, 0, 0]);
+        push_chunk(&mut png, b"IDAT", flate::deflate_bytes_zlib(raw).unwrap().as_slice());
+        push_chunk(&mut png, b"IEND", &[]);
+
+        let image = decode(png.as_slice()).unwrap();
+        assert_eq!((image.width, image.height), (2, 5));
+        for (y, row) in rows.iter().enumerate() {
+            for x in range(0, 2u) {
+                assert_eq!(image.pixel(x as u32, y as u32), [row[x * 3], row[x * 3 + 1], row[x * 3 + 2], 255]);
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+extern crate gl;
+extern crate cgmath;
+
+use std::cell::RefCell;
+use cgmath::{Vector3,Vector4};
+
+use backdrop;
+use camera;
+use christmas_star;
+use control;
+use game;
+use game::Object;
+use glutil;
+use glutil::reload;
+use glutil::state;
+use light;
+use light::Light;
+use shadow;
+use transform;
+
+/// Everything that is drawn in a frame, independent of where it is drawn to
+pub struct Scene {
+    pub camera: camera::Camera,
+    pub lights: light::LightList,
+    /// None draws without shadows
+    pub shadows: Option<shadow::ShadowMaps>,
+    pub objects: Vec<Box<game::Object + 'static>>,
+    /// color the frame is cleared to
+    pub clear_color: Vector4<f32>,
+    tracker: RefCell<state::StateTracker>,
+}
+
+impl Scene {
+    /// An empty scene without shadows
+    pub fn new() -> Scene {
+        Scene {
+            camera: camera::Camera::new(),
+            lights: light::LightList::new(),
+            shadows: None,
+            objects: Vec::new(),
+            clear_color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            tracker: RefCell::new(state::StateTracker::new()),
+        }
+    }
+
+    /// The spinning gold star with its lights and shadows.
+    /// A wall behind the star catches its shadow when backdrop is true
+    pub fn christmas(mode: &reload::SourceMode, backdrop: bool) -> Result<Scene, glutil::GlError> {
+        let mut scene = Scene::new();
+        scene.lights = default_lights();
+        try!(scene.lights.init(mode));
+        scene.shadows = Some(try!(shadow::ShadowMaps::new(mode, 1024)));
+        let mut star = default_star();
+        try!(star.init(mode));
+        scene.objects.push(box star);
+
+        if backdrop {
+            let mut wall = backdrop::Backdrop::wall(-1.0, 4.0);
+            try!(wall.init(mode));
+            scene.objects.push(box wall);
+        }
+        Ok(scene)
+    }
+
+    /// Draw into a width x height area of the current framebuffer
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        self.camera.set_viewport(width, height);
+        unsafe { gl::Viewport(0, 0, width as i32, height as i32); }
+    }
+
+    /// dt is the time since the last update in seconds
+    pub fn update(&mut self, cs: &control::State, dt: f32) -> Result<(), glutil::GlError> {
+        try!(self.lights.update(cs, dt));
+        for o in self.objects.iter_mut() {
+            try!(o.update(cs, dt));
+        }
+        Ok(())
+    }
+
+    /// Render a frame into the current framebuffer
+    pub fn draw(&mut self) -> Result<(), glutil::GlError> {
+        try!(self.lights.upload());
+        match self.shadows {
+            Some(ref mut s) => try!(s.render(&self.lights, self.objects.as_slice(), &self.tracker)),
+            None => (),
+        }
+        let c = self.clear_color;
+        try!(self.tracker.borrow_mut().clear(c.x, c.y, c.z, c.w));
+        let ctx = game::DrawContext {
+            camera: &self.camera,
+            state: &self.tracker,
+            shadows: self.shadows.as_ref(),
+        };
+        for o in self.objects.iter() {
+            try!(o.draw(&ctx));
+        }
+        try!(self.lights.draw(&ctx));
+        Ok(())
+    }
+}
+
+/// Warm key light, cool fill from the other side, two colored tree lights and a stage spot.
+/// The number keys choose which of them the arrow keys move. They still need to be initialized
+pub fn default_lights() -> light::LightList {
+    let mut key = light::directional::Light::new(Vector3::new(0.4, 0.5, 1.0));
+    key.color = Vector3::new(1.0, 0.9, 0.75);
+    key.ambient = Vector3::new(0.1, 0.1, 0.1);
+    let mut fill = light::directional::Light::new(Vector3::new(-0.6, 0.1, 0.5));
+    fill.color = Vector3::new(0.6, 0.7, 1.0);
+    fill.intensity = 0.4;
+    fill.ambient = Vector3::new(0.0, 0.0, 0.0);
+    let mut red = light::point::Light::new(Vector3::new(-0.7, -0.5, 0.4));
+    red.color = Vector3::new(1.0, 0.2, 0.1);
+    red.ambient = Vector3::new(0.0, 0.0, 0.0);
+    let mut green = light::point::Light::new(Vector3::new(0.7, -0.4, 0.4));
+    green.color = Vector3::new(0.2, 1.0, 0.3);
+    green.ambient = Vector3::new(0.0, 0.0, 0.0);
+    let mut spot = light::spot::Light::new(Vector3::new(0.0, 1.5, 1.0), Vector3::new(0.0, 0.0, 0.0));
+    spot.intensity = 1.5;
+    let mut lights = light::LightList::new();
+    let all: Vec<Box<Light + 'static>> = vec![box key, box fill, box red, box green, box spot];
+    for l in all.into_iter() {
+        // the list has room for far more than these
+        lights.push(l).unwrap_or_else(|e| panic!("{}", e));
+    }
+    lights
+}
+
+/// The gold star slowly turning like a tree topper. It still needs to be initialized
+pub fn default_star() -> christmas_star::ChristmasStar {
+    let mut star = christmas_star::ChristmasStar::new();
+    star.set_shading(christmas_star::Shading::CookTorrance);
+    star.set_environment(Some(Vector3::new(0.2, 0.2, 0.25)));
+    star.set_spin(Some(transform::Spin::new(Vector3::new(0.0, 1.0, 0.0), 0.5)));
+    star
+}
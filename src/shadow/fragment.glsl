@@ -0,0 +1,5 @@
+#include "common/header.glsl"
+
+// only the depth is written
+void main() {
+}
@@ -0,0 +1,157 @@
+extern crate gl;
+extern crate cgmath;
+
+use std::cell::RefCell;
+use gl::types::{GLint,GLsizei};
+use cgmath::{Matrix4,Vector3};
+
+use glutil;
+use glutil::framebuffer;
+use glutil::framebuffer::Framebuffer;
+use glutil::reload;
+use glutil::state;
+use glutil::texture::Texture;
+use light;
+use game;
+
+/// Maximum number of lights that cast shadows at once. Must match MAX_SHADOWS of common/shadow.glsl
+pub static MAX_SHADOWS: uint = 4;
+
+/// Texture unit the shadow maps are bound to while drawing
+pub static TEXTURE_UNIT: u32 = 1;
+
+/// Depth of the scene seen from each light that casts shadows, rendered every frame
+/// into the layers of one array texture.
+/// Lit objects read them through common/shadow.glsl after calling bind.
+pub struct ShadowMaps {
+    /// width and height of each shadow map in texels
+    resolution: GLsizei,
+    /// center and radius of the part of the scene that casts and receives shadows
+    pub center: Vector3<f32>,
+    pub radius: f32,
+    /// depth offset against shadow acne, in the [0, 1] depth range of the shadow map
+    pub constant_bias: f32,
+    /// extra offset for surfaces at a grazing angle to the light
+    pub slope_bias: f32,
+    /// lookups on each side of the texel for percentage closer filtering. 0 takes a single one
+    pub pcf_radius: i32,
+    texture: Texture,
+    framebuffer: Framebuffer,
+    program: reload::ReloadableProgram,
+    /// layer of each light of the last render, -1 for lights without a shadow
+    layers: Vec<i32>,
+    /// view projection matrix of each layer
+    matrices: Vec<Matrix4<f32>>,
+}
+
+impl ShadowMaps {
+    pub fn new(mode: &reload::SourceMode, resolution: GLsizei) -> Result<ShadowMaps, glutil::GlError> {
+        let texture = try!(Texture::depth_array(resolution, resolution, MAX_SHADOWS as GLsizei));
+        let previous = framebuffer::bound();
+        let framebuffer = try!(Framebuffer::new());
+        let status = framebuffer.attach_layer(gl::DEPTH_ATTACHMENT, &texture, 0)
+            .and_then(|_| framebuffer.disable_color())
+            .and_then(|_| framebuffer.check_status());
+        framebuffer::restore(previous);
+        try!(status);
+        let program = try!(reload::ReloadableProgram::new(mode,
+            reload::ShaderFile::new("shadow/vertex.glsl", include_str!("vertex.glsl")),
+            reload::ShaderFile::new("shadow/fragment.glsl", include_str!("fragment.glsl"))));
+        Ok(ShadowMaps {
+            resolution: resolution,
+            center: Vector3::new(0.0, 0.0, 0.0),
+            radius: 2.0,
+            constant_bias: 0.001,
+            slope_bias: 0.005,
+            pcf_radius: 1,
+            texture: texture,
+            framebuffer: framebuffer,
+            program: program,
+            layers: Vec::new(),
+            matrices: Vec::new(),
+        })
+    }
+
+    /// Render the depth of objects from every light that casts a shadow.
+    /// Lights past MAX_SHADOWS are lit without one.
+    /// The framebuffer that was bound, such as an offscreen target, and the viewport are restored afterwards
+    pub fn render(&mut self, lights: &light::LightList, objects: &[Box<game::Object + 'static>],
+        tracker: &RefCell<state::StateTracker>) -> Result<(), glutil::GlError> {
+        self.program.reload_if_changed();
+        self.layers.clear();
+        self.matrices.clear();
+        for vp in lights.shadow_view_projections(&self.center, self.radius).into_iter() {
+            match vp {
+                Some(m) if self.matrices.len() < MAX_SHADOWS => {
+                    self.layers.push(self.matrices.len() as i32);
+                    self.matrices.push(m);
+                },
+                _ => self.layers.push(-1),
+            }
+        }
+
+        let previous = framebuffer::bound();
+        let mut viewport = [0 as GLint, ..4];
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            gl::Viewport(0, 0, self.resolution, self.resolution);
+        }
+        let result = self.render_layers(objects, tracker);
+        framebuffer::restore(previous);
+        let [x, y, w, h] = viewport;
+        unsafe { gl::Viewport(x, y, w, h); }
+        result
+    }
+
+    fn render_layers(&self, objects: &[Box<game::Object + 'static>],
+        tracker: &RefCell<state::StateTracker>) -> Result<(), glutil::GlError> {
+        for (layer, vp) in self.matrices.iter().enumerate() {
+            try!(self.framebuffer.attach_layer(gl::DEPTH_ATTACHMENT, &self.texture, layer as GLint));
+            // there is no color attachment, so only the depth is cleared
+            try!(tracker.borrow_mut().clear(1.0, 1.0, 1.0, 1.0));
+            let ctx = game::DepthContext {
+                view_projection: *vp,
+                program: &*self.program,
+                state: tracker,
+            };
+            for o in objects.iter() {
+                try!(o.draw_depth(&ctx));
+            }
+        }
+        Ok(())
+    }
+
+    /// Bind the shadow maps and set the uniforms of common/shadow.glsl
+    pub fn bind(&self, program: &glutil::Program) -> Result<(), glutil::GlError> {
+        try!(self.texture.bind(TEXTURE_UNIT));
+        try!(program.set_optional_uniform("shadow_maps", &(TEXTURE_UNIT as i32)));
+        try!(set_layers(program, self.layers.as_slice()));
+        if !self.matrices.is_empty() {
+            try!(program.set_optional_uniform_array("shadow_matrices", self.matrices.as_slice()));
+        }
+        try!(program.set_optional_uniform("shadow_constant_bias", &self.constant_bias));
+        try!(program.set_optional_uniform("shadow_slope_bias", &self.slope_bias));
+        try!(program.set_optional_uniform("shadow_pcf_radius", &self.pcf_radius));
+        Ok(())
+    }
+}
+
+/// Set the uniforms of common/shadow.glsl from shadows, or turn shadows off in program when None
+pub fn bind(shadows: Option<&ShadowMaps>, program: &glutil::Program) -> Result<(), glutil::GlError> {
+    match shadows {
+        Some(s) => s.bind(program),
+        None => {
+            try!(program.set_optional_uniform("shadow_maps", &(TEXTURE_UNIT as i32)));
+            set_layers(program, &[])
+        },
+    }
+}
+
+/// Upload the layer of each light, and -1 for every light beyond them
+fn set_layers(program: &glutil::Program, layers: &[i32]) -> Result<(), glutil::GlError> {
+    let mut all = Vec::from_elem(light::MAX_LIGHTS, -1i32);
+    for (i, &l) in layers.iter().take(light::MAX_LIGHTS).enumerate() {
+        all[i] = l;
+    }
+    program.set_optional_uniform_array("shadow_layers", all.as_slice())
+}
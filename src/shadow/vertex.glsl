@@ -0,0 +1,10 @@
+#include "common/header.glsl"
+
+layout (location = 0) in vec3 position;
+
+// from the model to the clip space of the light
+uniform mat4 mvp;
+
+void main() {
+    gl_Position = mvp * vec4(position,1.0);
+}
@@ -0,0 +1,366 @@
+extern crate gl;
+extern crate cgmath;
+
+use std::num::Float;
+use cgmath::{Matrix,Point,Vector,Vector3,Vector4,EuclideanVector};
+
+use camera;
+use christmas_star;
+use glutil::state;
+use light;
+use light::gizmo;
+use material;
+use offscreen::Image;
+
+/// Renders the star and its lights on the CPU, for machines without a usable OpenGL.
+/// Shading follows the Blinn-Phong path of common/shading.glsl. Stars with Shading::CookTorrance
+/// are refused, and shadows are not reproduced.
+pub struct Renderer {
+    width: u32,
+    height: u32,
+    pub clear_color: Vector4<f32>,
+    /// RGBA of every pixel as the shaders would write it, rows from the top
+    color: Vec<Vector4<f32>>,
+    /// depth from 0.0 at the near plane to 1.0 at the far plane
+    depth: Vec<f32>,
+}
+
+/// Values interpolated over a triangle, like the outputs of christmas_star/vertex.glsl
+#[deriving(Clone)]
+struct Varying {
+    clip: Vector4<f32>,
+    world_position: Vector3<f32>,
+    world_normal: Vector3<f32>,
+}
+
+impl Varying {
+    fn lerp(&self, other: &Varying, t: f32) -> Varying {
+        Varying {
+            clip: self.clip.add_v(&other.clip.sub_v(&self.clip).mul_s(t)),
+            world_position: self.world_position.add_v(&other.world_position.sub_v(&self.world_position).mul_s(t)),
+            world_normal: self.world_normal.add_v(&other.world_normal.sub_v(&self.world_normal).mul_s(t)),
+        }
+    }
+}
+
+/// A vertex after the perspective divide and the viewport transform
+struct ScreenVertex {
+    x: f32,
+    y: f32,
+    z: f32,
+    /// 1 / w, for perspective-correct interpolation
+    inv_w: f32,
+}
+
+impl Renderer {
+    pub fn new(width: u32, height: u32) -> Renderer {
+        let size = (width * height) as uint;
+        Renderer {
+            width: width,
+            height: height,
+            clear_color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            color: Vec::from_elem(size, Vector4::new(0.0, 0.0, 0.0, 0.0)),
+            depth: Vec::from_elem(size, 1.0),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for c in self.color.iter_mut() {
+            *c = self.clear_color;
+        }
+        for d in self.depth.iter_mut() {
+            *d = 1.0;
+        }
+    }
+
+    /// Draw the triangles of star lit by lights with the render state of the star
+    pub fn draw_star(&mut self, star: &christmas_star::ChristmasStar, lights: &light::LightList,
+        camera: &camera::Camera) -> Result<(), String> {
+        if star.shading() != christmas_star::Shading::BlinnPhong {
+            return Err("the software renderer only supports Shading::BlinnPhong".to_string());
+        }
+        let render_state = star.render_state();
+        let model = star.model_matrix();
+        let mvp = camera.view_projection().mul_m(&model);
+        let normal_matrix = camera::normal_matrix(&model);
+        let params = lights.params();
+        let environment = star.environment().unwrap_or(Vector3::new(0.0, 0.0, 0.0));
+        let eye = camera.position.to_vec();
+        let m = star.mesh();
+
+        let varyings: Vec<Varying> = m.vertices.iter().map(|v| {
+            let p = v.position;
+            Varying {
+                clip: mvp.mul_v(&Vector4::new(p.x, p.y, p.z, 1.0)),
+                world_position: model.mul_v(&Vector4::new(p.x, p.y, p.z, 1.0)).truncate(),
+                world_normal: normal_matrix.mul_v(&v.normal),
+            }
+        }).collect();
+        for t in range(0, m.indices.len() / 3) {
+            let i0 = m.indices.get(t * 3) as uint;
+            let i1 = m.indices.get(t * 3 + 1) as uint;
+            let i2 = m.indices.get(t * 3 + 2) as uint;
+            // the facet is flat shaded with the normal of its provoking vertex, the last one, like the
+            // flat world_normal of christmas_star/vertex.glsl
+            let flat = |i: uint| Varying { world_normal: varyings[i2].world_normal, ..varyings[i].clone() };
+            for tri in clip_near(&flat(i0), &flat(i1), &varyings[i2]).iter() {
+                let (ref a, ref b, ref c) = *tri;
+                self.draw_triangle(a, b, c, render_state, |v| {
+                    let n = v.world_normal.normalize();
+                    let view = eye.sub_v(&v.world_position).normalize();
+                    shade(star.material(), params.as_slice(), &environment, &v.world_position, &n, &view)
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw the gizmo of every light with the primitive it is drawn with in GL
+    pub fn draw_light_gizmos(&mut self, lights: &light::LightList, camera: &camera::Camera) {
+        let color = gizmo::color();
+        let vp = camera.view_projection();
+        for &(lines, ref model) in lights.gizmos().iter() {
+            let mvp = vp.mul_m(model);
+            let clip: Vec<Vector4<f32>> = range(0, lines.mesh.indices.len()).map(|i| {
+                let p = lines.mesh.vertices[lines.mesh.indices.get(i) as uint].position;
+                mvp.mul_v(&Vector4::new(p.x, p.y, p.z, 1.0))
+            }).collect();
+            let n = clip.len();
+            let segments: Vec<(uint, uint)> = if lines.primitive == gl::LINES {
+                range(0, n / 2).map(|i| (i * 2, i * 2 + 1)).collect()
+            } else if lines.primitive == gl::LINE_STRIP {
+                range(1, n).map(|i| (i - 1, i)).collect()
+            } else if lines.primitive == gl::LINE_LOOP {
+                range(0, n).map(|i| (i, (i + 1) % n)).collect()
+            } else {
+                Vec::new()
+            };
+            for &(a, b) in segments.iter() {
+                self.draw_line(&clip[a], &clip[b], &color);
+            }
+        }
+    }
+
+    /// The color buffer with 8 bits per channel, the way glReadPixels returns it
+    pub fn image(&self) -> Image {
+        let mut image = Image::new(self.width, self.height);
+        for (i, c) in self.color.iter().enumerate() {
+            let channels = [c.x, c.y, c.z, c.w];
+            for ch in range(0, 4) {
+                image.pixels[i * 4 + ch] = (channels[ch].max(0.0).min(1.0) * 255.0 + 0.5) as u8;
+            }
+        }
+        image
+    }
+
+    fn to_screen(&self, clip: &Vector4<f32>) -> ScreenVertex {
+        let inv_w = 1.0 / clip.w;
+        ScreenVertex {
+            x: (clip.x * inv_w * 0.5 + 0.5) * self.width as f32,
+            // image rows go from the top while clip space y goes up
+            y: (0.5 - clip.y * inv_w * 0.5) * self.height as f32,
+            z: clip.z * inv_w * 0.5 + 0.5,
+            inv_w: inv_w,
+        }
+    }
+
+    fn draw_triangle(&mut self, a: &Varying, b: &Varying, c: &Varying, render_state: &state::RenderState,
+        fragment: |&Varying| -> Vector4<f32>) {
+        let s = [self.to_screen(&a.clip), self.to_screen(&b.clip), self.to_screen(&c.clip)];
+        // counter-clockwise faces are in front. y points down on screen, which flips the sign
+        let area = edge(&s[0], &s[1], s[2].x, s[2].y);
+        let culled = match render_state.cull {
+            Some(state::Cull::Back) => area >= 0.0,
+            Some(state::Cull::Front) => area <= 0.0,
+            None => area == 0.0,
+        };
+        if culled {
+            return;
+        }
+        let (min_x, max_x) = bounds(s.iter().map(|v| v.x));
+        let (min_y, max_y) = bounds(s.iter().map(|v| v.y));
+        if max_x < 0.0 || max_y < 0.0 || min_x >= self.width as f32 || min_y >= self.height as f32 {
+            return;
+        }
+        let (min_x, min_y) = (min_x.max(0.0) as u32, min_y.max(0.0) as u32);
+        let max_x = max_x.min(self.width as f32 - 1.0) as u32;
+        let max_y = max_y.min(self.height as f32 - 1.0) as u32;
+        for y in range(min_y, max_y + 1) {
+            for x in range(min_x, max_x + 1) {
+                // sample at the pixel center like GL does
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let w0 = edge(&s[1], &s[2], px, py) / area;
+                let w1 = edge(&s[2], &s[0], px, py) / area;
+                let w2 = edge(&s[0], &s[1], px, py) / area;
+                if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                    continue;
+                }
+                // depth is affine in screen space, everything else is interpolated over 1 / w
+                let z = w0 * s[0].z + w1 * s[1].z + w2 * s[2].z;
+                let i = (y * self.width + x) as uint;
+                if z < 0.0 || (render_state.depth_test && z >= self.depth[i]) {
+                    continue;
+                }
+                let (p0, p1, p2) = (w0 * s[0].inv_w, w1 * s[1].inv_w, w2 * s[2].inv_w);
+                let sum = p0 + p1 + p2;
+                let v = interpolate(a, b, c, p0 / sum, p1 / sum, p2 / sum);
+                let color = fragment(&v);
+                if render_state.depth_write {
+                    self.depth[i] = z;
+                }
+                self.write(i, &color, &render_state.blend);
+            }
+        }
+    }
+
+    /// Draw a line between two points in clip space without culling, as the gizmos are drawn
+    fn draw_line(&mut self, a: &Vector4<f32>, b: &Vector4<f32>, color: &Vector4<f32>) {
+        if a.w <= 0.0 || b.w <= 0.0 {
+            // behind the camera
+            return;
+        }
+        let (s0, s1) = (self.to_screen(a), self.to_screen(b));
+        let steps = (s1.x - s0.x).abs().max((s1.y - s0.y).abs()).ceil().max(1.0) as uint;
+        for step in range(0, steps + 1) {
+            let t = step as f32 / steps as f32;
+            let x = s0.x + (s1.x - s0.x) * t;
+            let y = s0.y + (s1.y - s0.y) * t;
+            let z = s0.z + (s1.z - s0.z) * t;
+            if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+                continue;
+            }
+            let i = (y as u32 * self.width + x as u32) as uint;
+            if z < 0.0 || z >= self.depth[i] {
+                continue;
+            }
+            self.depth[i] = z;
+            self.write(i, color, &None);
+        }
+    }
+
+    /// Store color in pixel i, combined with what is there the way the GL blend functions do
+    fn write(&mut self, i: uint, color: &Vector4<f32>, blend: &Option<state::Blend>) {
+        let dst = self.color[i];
+        self.color[i] = match *blend {
+            None => *color,
+            Some(state::Blend::Alpha) => color.mul_s(color.w).add_v(&dst.mul_s(1.0 - color.w)),
+            Some(state::Blend::Additive) => color.add_v(&dst),
+        };
+    }
+}
+
+/// Render star and the gizmos of lights as seen by camera into a width x height image
+pub fn render(star: &christmas_star::ChristmasStar, lights: &light::LightList, camera: &mut camera::Camera,
+    width: u32, height: u32, clear_color: Vector4<f32>) -> Result<Image, String> {
+    camera.set_viewport(width, height);
+    let mut renderer = Renderer::new(width, height);
+    renderer.clear_color = clear_color;
+    renderer.clear();
+    try!(renderer.draw_star(star, lights, camera));
+    renderer.draw_light_gizmos(lights, camera);
+    Ok(renderer.image())
+}
+
+/// Smallest and largest of values
+fn bounds<I: Iterator<f32>>(mut values: I) -> (f32, f32) {
+    values.fold((Float::infinity(), Float::neg_infinity()), |(lo, hi), v| (lo.min(v), hi.max(v)))
+}
+
+/// Twice the signed area of the triangle a, b, p
+fn edge(a: &ScreenVertex, b: &ScreenVertex, px: f32, py: f32) -> f32 {
+    (b.x - a.x) * (py - a.y) - (b.y - a.y) * (px - a.x)
+}
+
+fn interpolate(a: &Varying, b: &Varying, c: &Varying, w0: f32, w1: f32, w2: f32) -> Varying {
+    let mix3 = |x: &Vector3<f32>, y: &Vector3<f32>, z: &Vector3<f32>| {
+        x.mul_s(w0).add_v(&y.mul_s(w1)).add_v(&z.mul_s(w2))
+    };
+    Varying {
+        clip: a.clip.mul_s(w0).add_v(&b.clip.mul_s(w1)).add_v(&c.clip.mul_s(w2)),
+        world_position: mix3(&a.world_position, &b.world_position, &c.world_position),
+        world_normal: mix3(&a.world_normal, &b.world_normal, &c.world_normal),
+    }
+}
+
+/// Cut away the part of a triangle in front of the near plane, where z < -w.
+/// What remains is a polygon of up to four vertices, returned as triangles in the same winding
+fn clip_near(a: &Varying, b: &Varying, c: &Varying) -> Vec<(Varying, Varying, Varying)> {
+    let input = [a, b, c];
+    let distance = |v: &Varying| v.clip.z + v.clip.w;
+    let mut polygon = Vec::new();
+    for i in range(0, 3u) {
+        let (cur, next) = (input[i], input[(i + 1) % 3]);
+        let (d0, d1) = (distance(cur), distance(next));
+        if d0 >= 0.0 {
+            polygon.push(cur.clone());
+        }
+        if (d0 >= 0.0) != (d1 >= 0.0) {
+            polygon.push(cur.lerp(next, d0 / (d0 - d1)));
+        }
+    }
+    let mut triangles = Vec::new();
+    if polygon.len() < 3 {
+        // entirely in front of the near plane
+        return triangles;
+    }
+    for i in range(1, polygon.len() - 1) {
+        triangles.push((polygon[0].clone(), polygon[i].clone(), polygon[i + 1].clone()));
+    }
+    triangles
+}
+
+/// Color of a surface lit by lights. Mirrors the Blinn-Phong path of shade() in common/shading.glsl
+fn shade(m: &material::Material, lights: &[light::Params], environment: &Vector3<f32>,
+    position: &Vector3<f32>, n: &Vector3<f32>, v: &Vector3<f32>) -> Vector4<f32> {
+    let base = m.base_color.truncate();
+    let mut ambient = *environment;
+    for l in lights.iter() {
+        ambient = ambient.add_v(&l.ambient);
+    }
+    let mut color = ambient.mul_v(&base).add_v(&m.emissive);
+    for l in lights.iter() {
+        let (dir, intensity) = incident_intensity(l, position);
+        let radiance = l.color.mul_s(intensity);
+        color = color.add_v(&base.mul_v(&radiance).mul_s(lambert(n, &dir)));
+        let specular = blinn_phong_specular(n, &dir, v, m.shininess);
+        color = color.add_v(&m.specular.mul_v(&radiance).mul_s(specular));
+    }
+    Vector4::new(color.x, color.y, color.z, m.base_color.w)
+}
+
+/// Normalized direction towards the light and the fraction of its color that arrives.
+/// Mirrors incident_intensity() in common/light.glsl
+fn incident_intensity(l: &light::Params, position: &Vector3<f32>) -> (Vector3<f32>, f32) {
+    if l.position.w == 0.0 {
+        return (l.position.truncate().normalize(), l.intensity);
+    }
+    let to_light = l.position.truncate().sub_v(position);
+    let d = to_light.length();
+    let dir = to_light.div_s(d.max(1e-4));
+    let attenuation = l.attenuation.x + l.attenuation.y * d + l.attenuation.z * d * d;
+    let (cos_inner, cos_outer) = l.spot_cos;
+    let mut cone = 1.0;
+    if cos_outer > -1.0 {
+        let cos_angle = dir.mul_s(-1.0).dot(&l.spot_direction.normalize());
+        cone = smoothstep(cos_outer, cos_inner.max(cos_outer + 1e-4), cos_angle);
+    }
+    (dir, l.intensity * cone / attenuation.max(1e-4))
+}
+
+fn lambert(n: &Vector3<f32>, l: &Vector3<f32>) -> f32 {
+    n.dot(l).max(0.0)
+}
+
+fn blinn_phong_specular(n: &Vector3<f32>, l: &Vector3<f32>, v: &Vector3<f32>, shininess: f32) -> f32 {
+    if n.dot(l) <= 0.0 {
+        // no highlight on faces turned away from the light
+        return 0.0;
+    }
+    let h = l.add_v(v).normalize();
+    n.dot(&h).max(0.0).powf(shininess)
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).max(0.0).min(1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,55 @@
+extern crate cgmath;
+
+use cgmath::{Matrix,Matrix4,Quaternion,Vector3,Vector4,EuclideanVector,ToMatrix4,Rotation3,rad};
+
+/// Placement of an object in the world.
+/// Applied as scale, then rotation, then translation.
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform {
+            translation: Vector3::new(0.0, 0.0, 0.0),
+            rotation: Quaternion::identity(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    /// Rotate further by angle radians around axis
+    pub fn rotate(&mut self, axis: &Vector3<f32>, angle: f32) {
+        let q : Quaternion<f32> = Rotation3::from_axis_angle(&axis.normalize(), rad(angle));
+        // renormalize so accumulated rounding errors don't turn into a scale
+        self.rotation = q.mul_q(&self.rotation).normalize();
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        let t = Matrix4::from_translation(&self.translation);
+        let r = self.rotation.to_matrix4();
+        let s = Matrix4::from_diagonal(&Vector4::new(self.scale.x, self.scale.y, self.scale.z, 1.0));
+        t.mul_m(&r).mul_m(&s)
+    }
+}
+
+/// Continuous rotation around axis at speed radians per second
+pub struct Spin {
+    pub axis: Vector3<f32>,
+    pub speed: f32,
+}
+
+impl Spin {
+    pub fn new(axis: Vector3<f32>, speed: f32) -> Spin {
+        Spin {
+            axis: axis,
+            speed: speed,
+        }
+    }
+
+    /// Advance transform by dt seconds
+    pub fn apply(&self, transform: &mut Transform, dt: f32) {
+        transform.rotate(&self.axis, self.speed * dt);
+    }
+}
@@ -0,0 +1,116 @@
+//! Renders fixed scenes through the offscreen path and compares them against the PNG files
+//! in tests/reference.
+//! Setting BLESS_GOLDEN=1 records all of them from the current output, after an intended change
+//! of the look. A missing reference fails the test, so a normal run never writes into the source tree.
+//! On a mismatch the output and a diff image are written to tests/output.
+
+extern crate cgmath;
+extern crate christmas_star;
+
+use std::io::fs;
+use std::os;
+use cgmath::Vector3;
+
+use christmas_star::{backdrop,christmas_star,compare,light,offscreen,png,scene,shadow};
+use christmas_star::glutil::reload::SourceMode;
+
+static WIDTH: u32 = 256;
+static HEIGHT: u32 = 256;
+
+/// The star of the scene, facing the camera after a fixed turn so the facets catch the light
+fn star(shading: christmas_star::Shading) -> christmas_star::ChristmasStar {
+    let mut star = christmas_star::ChristmasStar::with_geometry(christmas_star::Geometry::classic());
+    star.set_shading(shading);
+    star.transform_mut().rotate(&Vector3::new(0.0, 1.0, 0.0), 0.4);
+    star
+}
+
+/// A point light where the original light of the star used to be
+fn fixed_scene(star: christmas_star::ChristmasStar) -> scene::Scene {
+    let mut scene = scene::Scene::new();
+    scene.lights.push(box light::point::Light::new(Vector3::new(0.4, 0.5, 1.0)))
+        .unwrap_or_else(|e| panic!("{}", e));
+    scene.lights.init(&SourceMode::Embedded).unwrap_or_else(|e| panic!("{}", e));
+    let mut star = star;
+    star.init(&SourceMode::Embedded).unwrap_or_else(|e| panic!("{}", e));
+    scene.objects.push(box star);
+    scene
+}
+
+fn path(dir: &str, name: &str) -> Path {
+    Path::new(file!()).dir_path().join(dir).join(name)
+}
+
+/// Render the scene made by build and compare it against the reference called name.
+/// The context is created before build, since the scene creates GL objects
+fn check(name: &str, build: || -> scene::Scene) {
+    let _context = offscreen::create_context(WIDTH, HEIGHT).unwrap_or_else(|e| panic!("{}", e));
+    let mut scene = build();
+    let actual = offscreen::render(&mut scene, WIDTH, HEIGHT).unwrap_or_else(|e| panic!("{}", e));
+
+    let file = format!("{}.png", name);
+    let reference = path("reference", file.as_slice());
+    let bless = os::getenv("BLESS_GOLDEN").map_or(false, |v| v.as_slice() == "1");
+    if bless {
+        let _ = fs::mkdir_recursive(&reference.dir_path(), std::io::USER_RWX);
+        png::write(&reference, &actual, true).unwrap_or_else(|e| panic!("{}", e));
+        println!("recorded {}", reference.display());
+        return;
+    }
+    if !reference.exists() {
+        panic!("{}: there is no reference {}. Record it with BLESS_GOLDEN=1 and check it before committing",
+            name, reference.display());
+    }
+    let expected = png::read(&reference).unwrap_or_else(|e| panic!("{}", e));
+    let result = compare::compare(&actual, &expected, &compare::Tolerance::new())
+        .unwrap_or_else(|e| panic!("{}: {}", name, e));
+    if !result.passed() {
+        let output = Path::new(file!()).dir_path().join("output");
+        let _ = fs::mkdir_recursive(&output, std::io::USER_RWX);
+        let actual_path = output.join(format!("{}-actual.png", name));
+        let diff_path = output.join(format!("{}-diff.png", name));
+        png::write(&actual_path, &actual, true).unwrap_or_else(|e| panic!("{}", e));
+        png::write(&diff_path, &result.diff, false).unwrap_or_else(|e| panic!("{}", e));
+        panic!("{}: {} of {} pixels differ (max channel {}, max delta E {}). See {} and {}",
+            name, result.mismatched, result.total, result.max_channel, result.max_perceptual,
+            actual_path.display(), diff_path.display());
+    }
+}
+
+#[test]
+fn blinn_phong() {
+    check("blinn_phong", || fixed_scene(star(christmas_star::Shading::BlinnPhong)));
+}
+
+#[test]
+fn cook_torrance() {
+    check("cook_torrance", || fixed_scene(star(christmas_star::Shading::CookTorrance)));
+}
+
+#[test]
+fn transparent_background() {
+    check("transparent_background", || {
+        let mut scene = fixed_scene(star(christmas_star::Shading::BlinnPhong));
+        scene.clear_color.w = 0.0;
+        scene
+    });
+}
+
+#[test]
+fn shadow_on_backdrop() {
+    check("shadow_on_backdrop", || {
+        let mut scene = scene::Scene::new();
+        scene.lights.push(box light::directional::Light::new(Vector3::new(0.4, 0.5, 1.0)))
+            .unwrap_or_else(|e| panic!("{}", e));
+        scene.lights.init(&SourceMode::Embedded).unwrap_or_else(|e| panic!("{}", e));
+        scene.shadows = Some(shadow::ShadowMaps::new(&SourceMode::Embedded, 512)
+            .unwrap_or_else(|e| panic!("{}", e)));
+        let mut star = star(christmas_star::Shading::BlinnPhong);
+        star.init(&SourceMode::Embedded).unwrap_or_else(|e| panic!("{}", e));
+        scene.objects.push(box star);
+        let mut wall = backdrop::Backdrop::wall(-1.0, 4.0);
+        wall.init(&SourceMode::Embedded).unwrap_or_else(|e| panic!("{}", e));
+        scene.objects.push(box wall);
+        scene
+    });
+}
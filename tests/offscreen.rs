@@ -0,0 +1,16 @@
+extern crate christmas_star;
+
+use christmas_star::{offscreen,scene};
+use christmas_star::glutil::reload::SourceMode;
+
+static SIZE: u32 = 64;
+
+#[test]
+fn shadowed_scene_is_drawn_into_the_target() {
+    let _context = offscreen::create_context(SIZE, SIZE).unwrap_or_else(|e| panic!("{}", e));
+    let mut scene = scene::Scene::christmas(&SourceMode::Embedded, false).unwrap_or_else(|e| panic!("{}", e));
+    assert!(scene.shadows.is_some());
+    let image = offscreen::render(&mut scene, SIZE, SIZE).unwrap_or_else(|e| panic!("{}", e));
+    // the star covers the center, so it must differ from the white clear color
+    assert!(image.pixel(SIZE / 2, SIZE / 2) != [255, 255, 255, 255]);
+}
@@ -0,0 +1,41 @@
+extern crate christmas_star;
+
+use christmas_star::offscreen::Image;
+use christmas_star::png;
+
+fn gradient(width: u32, height: u32) -> Image {
+    let mut image = Image::new(width, height);
+    for y in range(0, height) {
+        for x in range(0, width) {
+            let i = ((y * width + x) * 4) as uint;
+            image.pixels[i] = (x * 255 / width) as u8;
+            image.pixels[i + 1] = (y * 255 / height) as u8;
+            image.pixels[i + 2] = ((x + y) * 7) as u8;
+            image.pixels[i + 3] = if x % 2 == 0 { 255 } else { 128 };
+        }
+    }
+    image
+}
+
+#[test]
+fn rgba_round_trip() {
+    let image = gradient(13, 7);
+    let decoded = png::decode(png::encode(&image, true).as_slice()).unwrap();
+    assert_eq!((decoded.width, decoded.height), (13, 7));
+    assert_eq!(decoded.pixels, image.pixels);
+}
+
+#[test]
+fn rgb_drops_alpha() {
+    let image = gradient(5, 3);
+    let decoded = png::decode(png::encode(&image, false).as_slice()).unwrap();
+    for (d, i) in decoded.pixels.chunks(4).zip(image.pixels.chunks(4)) {
+        assert_eq!(d.slice_to(3), i.slice_to(3));
+        assert_eq!(d[3], 255);
+    }
+}
+
+#[test]
+fn rejects_other_files() {
+    assert!(png::decode(b"GIF89a").is_err());
+}
@@ -0,0 +1,97 @@
+//! Renders through the CPU rasterizer, which needs no GL and runs on any machine
+
+extern crate cgmath;
+extern crate christmas_star;
+
+use cgmath::{Vector3,Vector4};
+
+use christmas_star::{camera,christmas_star,light,software};
+use christmas_star::glutil::state;
+use christmas_star::offscreen::Image;
+
+static WIDTH: u32 = 128;
+static HEIGHT: u32 = 128;
+
+fn lights() -> light::LightList {
+    let mut lights = light::LightList::new();
+    lights.push(box light::point::Light::new(Vector3::new(0.4, 0.5, 1.0)))
+        .unwrap_or_else(|e| panic!("{}", e));
+    lights
+}
+
+fn render(star: &christmas_star::ChristmasStar, lights: &light::LightList, clear_alpha: f32) -> Image {
+    software::render(star, lights, &mut camera::Camera::new(), WIDTH, HEIGHT,
+        Vector4::new(1.0, 1.0, 1.0, clear_alpha)).unwrap_or_else(|e| panic!("{}", e))
+}
+
+#[test]
+fn star_covers_the_center() {
+    let star = christmas_star::ChristmasStar::new();
+    let image = render(&star, &light::LightList::new(), 1.0);
+    assert!(image.pixel(WIDTH / 2, HEIGHT / 2) != [255, 255, 255, 255]);
+    assert_eq!(image.pixel(0, 0), [255, 255, 255, 255]);
+}
+
+#[test]
+fn transparent_background() {
+    let star = christmas_star::ChristmasStar::new();
+    let image = render(&star, &lights(), 0.0);
+    assert_eq!(image.pixel(0, 0)[3], 0);
+    assert_eq!(image.pixel(WIDTH / 2, HEIGHT / 2)[3], 255);
+}
+
+#[test]
+fn light_brightens_the_star() {
+    let star = christmas_star::ChristmasStar::new();
+    let dark = render(&star, &light::LightList::new(), 1.0);
+    let lit = render(&star, &lights(), 1.0);
+    let sum = |image: &Image| {
+        let p = image.pixel(WIDTH / 2, HEIGHT / 2);
+        p[0] as uint + p[1] as uint + p[2] as uint
+    };
+    assert!(sum(&lit) > sum(&dark));
+}
+
+#[test]
+fn refuses_cook_torrance() {
+    let mut star = christmas_star::ChristmasStar::new();
+    star.set_shading(christmas_star::Shading::CookTorrance);
+    let mut renderer = software::Renderer::new(WIDTH, HEIGHT);
+    assert!(renderer.draw_star(&star, &lights(), &camera::Camera::new()).is_err());
+}
+
+#[test]
+fn culls_front_faces_when_asked() {
+    let mut star = christmas_star::ChristmasStar::new();
+    let back = render(&star, &lights(), 1.0);
+    star.set_cull(Some(state::Cull::Front));
+    let front = render(&star, &lights(), 1.0);
+    // the back of the star is seen instead, which faces away from the light
+    assert!(back.pixels != front.pixels);
+}
+
+#[test]
+fn draws_light_gizmos() {
+    let mut lights = lights();
+    lights.push(box light::directional::Light::new(Vector3::new(0.0, 1.0, 0.2)))
+        .unwrap_or_else(|e| panic!("{}", e));
+    lights.push(box light::spot::Light::new(Vector3::new(-0.5, 0.5, 0.5), Vector3::new(0.0, 0.0, 0.0)))
+        .unwrap_or_else(|e| panic!("{}", e));
+    let mut renderer = software::Renderer::new(WIDTH, HEIGHT);
+    renderer.clear();
+    renderer.draw_light_gizmos(&lights, &camera::Camera::new());
+    let image = renderer.image();
+    let red = image.pixels.as_slice().chunks(4).filter(|p| p[0] == 255 && p[1] == 0 && p[2] == 0).count();
+    assert!(red > 0);
+}
+
+#[test]
+fn refuses_more_than_max_lights() {
+    let mut lights = light::LightList::new();
+    for i in range(0, light::MAX_LIGHTS) {
+        assert_eq!(lights.push(box light::point::Light::new(Vector3::new(0.0, 0.0, 1.0))), Ok(i));
+    }
+    assert_eq!(lights.push(box light::point::Light::new(Vector3::new(0.0, 0.0, 1.0))),
+        Err(light::LightError::TooMany { max: light::MAX_LIGHTS }));
+    assert_eq!(lights.len(), light::MAX_LIGHTS);
+}